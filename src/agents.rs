@@ -1,19 +1,30 @@
-use crate::models::Config;
-use crate::services::QuestClient;
-use log::info;
+use crate::models::{Config, StoredQuest};
+use crate::services::{
+    accounts::AccountPool, progress::ProgressTracker, storage::QuestStore, QuestClient,
+};
+use log::{info, warn};
 
 pub async fn agent_cycle(
     client: &QuestClient,
+    accounts: &AccountPool,
+    store: &dyn QuestStore,
     config: &Config,
     locales: &[String],
+    progress: Option<&ProgressTracker>,
 ) -> Result<(), String> {
     let locale = locales.first().map_or("en-US", String::as_str);
     info!("agent fetching locale {locale}");
-    let quests = client
-        .fetch_quests_with_locale(&config.discord.token, locale)
+    let (fetched, account) = client
+        .fetch_quests_from_pool(accounts, locale)
         .await
         .map_err(|e| format!("failed to fetch quests for agent {locale}: {e}"))?;
 
+    if let Some(tracker) = progress {
+        if let Err(e) = tracker.track(&fetched.quests, &account.name).await {
+            warn!("{e}");
+        }
+    }
+
     let Some(url) = config.collector_url() else {
         return Err("collector_url not configured".to_string());
     };
@@ -21,10 +32,36 @@ pub async fn agent_cycle(
         return Err("collector_token not configured".to_string());
     };
 
+    if !fetched.undecoded.is_empty() {
+        warn!(
+            "{} quests for {locale} failed to decode and will not be sent ({} since startup)",
+            fetched.undecoded.len(),
+            client.decode_failures()
+        );
+    }
+
+    // keep a local record of what this agent reported, for its own logs
+    let reported: Vec<StoredQuest> = fetched
+        .quests
+        .iter()
+        .map(|q| {
+            let mut sq = StoredQuest::from(q);
+            sq.id = format!("{locale}:{}", sq.id);
+            sq.seen_by = Some(account.name.clone());
+            sq
+        })
+        .collect();
+    let new_count = store
+        .find_new(&reported)
+        .map_err(|e| format!("failed to load stored quests: {e}"))?
+        .len();
+
     let payload = serde_json::json!({
         "region": locale,
-        "quests": quests,
-        "source": "agent"
+        "quests": fetched.quests,
+        "excluded_quests": fetched.excluded,
+        "source": "agent",
+        "account": account.name
     });
 
     let client_http = reqwest::Client::new();
@@ -40,6 +77,8 @@ pub async fn agent_cycle(
         return Err(format!("collector responded with status {}", res.status()));
     }
 
-    info!("agent payload sent successfully to {url}");
-    Ok(())
+    info!("agent payload sent successfully to {url} ({new_count} new since last report)");
+    store
+        .upsert(&reported)
+        .map_err(|e| format!("failed to save reported quests: {e}"))
 }
@@ -0,0 +1,172 @@
+//! Minimal stand-in for the Discord quests API.
+//!
+//! Serves `QuestsResponse` fixtures from a directory, picking `<locale>.json`
+//! from the `X-Discord-Locale` header and falling back to `default.json`.
+//! Point `[discord] api_base` at `http://127.0.0.1:<port>/api` to use it.
+//! `users/@me` and `webhooks/{id}/{token}` are stubbed as well, so startup
+//! validation and notifications can run against the same server.
+//!
+//! Usage: `cargo run --bin mock_discord -- [--port 9090] [--fixtures ./fixtures/quests] [--reject-token TOKEN]...`
+
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::Router;
+use log::{info, warn};
+use qwesty::models::QuestsResponse;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const EMPTY_RESPONSE: &str = r#"{"quests":[],"excluded_quests":[]}"#;
+
+struct MockState {
+    fixtures_dir: PathBuf,
+    /// Tokens answered with 401, to exercise account failover
+    rejected_tokens: Vec<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::Builder::from_default_env()
+        .filter_level(log::LevelFilter::Info)
+        .init();
+
+    let mut port: u16 = 9090;
+    let mut fixtures_dir = PathBuf::from("./fixtures/quests");
+    let mut rejected_tokens = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                if let Some(p) = args.next().and_then(|v| v.parse().ok()) {
+                    port = p;
+                }
+            }
+            "--fixtures" => {
+                if let Some(dir) = args.next() {
+                    fixtures_dir = PathBuf::from(dir);
+                }
+            }
+            "--reject-token" => rejected_tokens.extend(args.next()),
+            other => warn!("ignoring unknown argument: {other}"),
+        }
+    }
+
+    let state = Arc::new(MockState {
+        fixtures_dir,
+        rejected_tokens,
+    });
+    let app = Router::new()
+        .route("/api/:version/quests/@me", get(quests_handler))
+        .route("/api/:version/users/@me", get(user_handler))
+        .route(
+            "/api/webhooks/:id/:token",
+            get(webhook_get_handler).merge(post(webhook_post_handler)),
+        )
+        .with_state(state.clone());
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    info!(
+        "mock Discord API listening on http://{addr}/api (fixtures: {})",
+        state.fixtures_dir.display()
+    );
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .expect("failed to bind mock listener");
+    axum::serve(listener, app)
+        .await
+        .expect("mock server failed");
+}
+
+fn is_authorized(state: &MockState, headers: &HeaderMap) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|t| !state.rejected_tokens.iter().any(|r| r == t))
+}
+
+fn unauthorized() -> (StatusCode, [(header::HeaderName, &'static str); 1], String) {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::CONTENT_TYPE, "application/json")],
+        r#"{"message":"401: Unauthorized","code":0}"#.to_string(),
+    )
+}
+
+async fn user_handler(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !is_authorized(&state, &headers) {
+        return unauthorized();
+    }
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        r#"{"id":"1","username":"mock-user"}"#.to_string(),
+    )
+}
+
+async fn webhook_get_handler(Path((id, _token)): Path<(String, String)>) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        format!(r#"{{"id":"{id}","name":"mock-webhook"}}"#),
+    )
+}
+
+async fn webhook_post_handler(
+    Path((id, _token)): Path<(String, String)>,
+    body: String,
+) -> StatusCode {
+    info!("webhook {id} received {} bytes", body.len());
+    StatusCode::NO_CONTENT
+}
+
+async fn quests_handler(
+    State(state): State<Arc<MockState>>,
+    Path(version): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !is_authorized(&state, &headers) {
+        return unauthorized();
+    }
+
+    let locale = headers
+        .get("X-Discord-Locale")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("en-US");
+
+    let body = load_fixture(&state.fixtures_dir, locale);
+    match serde_json::from_str::<QuestsResponse>(&body) {
+        Ok(response) => {
+            for bad in response.decode().undecoded {
+                warn!(
+                    "fixture quest {} for {locale} does not decode as Quest: {}",
+                    bad.id.as_deref().unwrap_or("(no id)"),
+                    bad.error
+                );
+            }
+        }
+        Err(e) => warn!("fixture for {locale} does not decode as QuestsResponse: {e}"),
+    }
+
+    info!("served quests for locale {locale} (api {version})");
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        body,
+    )
+}
+
+/// Read `<locale>.json`, then `default.json`, re-reading on every request so
+/// fixtures can be edited while the server runs.
+fn load_fixture(dir: &std::path::Path, locale: &str) -> String {
+    [format!("{locale}.json"), "default.json".to_string()]
+        .iter()
+        .find_map(|name| std::fs::read_to_string(dir.join(name)).ok())
+        .unwrap_or_else(|| EMPTY_RESPONSE.to_string())
+}
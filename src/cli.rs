@@ -0,0 +1,43 @@
+/// What the binary should do, chosen by the first command-line argument.
+pub enum Command {
+    /// Default: run the notifier loop
+    Run,
+    /// Print the decoded X-Super-Properties for every account and exit
+    SuperProperties,
+    /// Feed archived responses through the collector without calling Discord
+    /// or sending webhooks; the directory defaults to `[archive] dir`
+    Replay { dir: Option<String> },
+    /// Copy quests from a `known-quests.json` file into the configured
+    /// SQLite database, skipping ones it already has
+    Import { path: String },
+    /// Print when each collector locale was last fetched and exit
+    Locales,
+    /// Apply the `[retention]` policy to the configured store once and exit
+    Prune,
+}
+
+impl Command {
+    /// Parse the command from `std::env::args`.
+    ///
+    /// # Errors
+    /// Returns a usage message if the command is not recognised.
+    pub fn from_args() -> Result<Self, String> {
+        match std::env::args().nth(1).as_deref() {
+            None | Some("run") => Ok(Self::Run),
+            Some("super-properties") => Ok(Self::SuperProperties),
+            Some("replay") => Ok(Self::Replay {
+                dir: std::env::args().nth(2),
+            }),
+            Some("import") => Ok(Self::Import {
+                path: std::env::args()
+                    .nth(2)
+                    .unwrap_or_else(|| "./known-quests.json".to_string()),
+            }),
+            Some("locales") => Ok(Self::Locales),
+            Some("prune") => Ok(Self::Prune),
+            Some(other) => Err(format!(
+                "unknown command: {other}\n\nusage: qwesty [run | super-properties | replay [DIR] | import [FILE] | locales | prune]"
+            )),
+        }
+    }
+}
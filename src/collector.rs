@@ -1,155 +1,354 @@
-use crate::services::{storage, webhook::WebhookNotifier, QuestClient};
+use crate::models::{CycleSummary, LocaleOutcome, Quest, StoredQuest, EXCLUDED_ID_PREFIX};
+use crate::scheduler;
+use crate::services::{
+    checkpoint::CycleCheckpoint,
+    delivery,
+    progress::ProgressTracker,
+    source::QuestSource,
+    storage::{self, StoreHandle},
+    webhook::WebhookNotifier,
+};
 use crate::utils::dedupe_by_key;
-use log::{debug, info};
+use chrono::Utc;
+use futures_util::future::join_all;
+use log::{debug, error, info, warn};
 use std::collections::HashSet;
-use std::time::Duration;
-use tokio::time::sleep;
+use tokio::sync::oneshot;
 
 pub async fn check_quests_all_locales(
-    client: &QuestClient,
+    source: &QuestSource<'_>,
+    store_handle: &StoreHandle,
     notifiers: &[WebhookNotifier],
-    token: &str,
     reward_filter: &str,
-    locales: &[String],
+    checkpoint: &CycleCheckpoint,
     initial_send_all: bool,
-) -> Result<(), String> {
+    progress: Option<&ProgressTracker>,
+) -> Result<CycleSummary, String> {
+    let mut summary = CycleSummary::default();
     let mut all_filtered_quests = Vec::new();
-    let mut stored = storage::load_stored_quests();
+    let load_err = |e| format!("failed to load stored quests: {e}");
+    let mut stored = store_handle.lock().await.load().map_err(load_err)?;
     let seed_only = stored.is_empty() && !initial_send_all;
+    // new quests are announced once at the end of the cycle, when it is known
+    // which locales list them
+    let mut new_quests: Vec<Quest> = Vec::new();
+    let mut new_entries: Vec<StoredQuest> = Vec::new();
+    // entries of known quests in locales they weren't listed in before
+    let mut new_regions: Vec<StoredQuest> = Vec::new();
 
-    for (index, locale) in locales.iter().enumerate() {
-        if index > 0 {
-            let delay_secs: u64 = 60 + u64::from(rand::random::<u8>() % 11); // 60..70
-            info!("waiting {delay_secs} seconds before checking next locale ({locale})");
-            sleep(Duration::from_secs(delay_secs)).await;
-        }
+    // every locale is fetched at once; the client's request budget decides
+    // how fast they actually go out. Requests are queued, and results
+    // handled, stalest locale first so an interrupted cycle picks up where it
+    // was cut off. The fetches run alongside the handling rather than inside
+    // it, so a slow webhook neither leaves budget slots idle nor lets a
+    // response sit until its request times out.
+    let locales = checkpoint.locales();
+    let order = checkpoint.stalest_first();
+    let (senders, receivers): (Vec<_>, Vec<_>) = order.iter().map(|_| oneshot::channel()).unzip();
+    let fetch_all = join_all(order.iter().zip(senders).map(|(locale, tx)| async move {
+        // the receiver is only gone once handling stopped on an error
+        let _ = tx.send(source.fetch(locale).await);
+    }));
+    let handle_all = async {
+        for (locale, rx) in order.iter().zip(receivers) {
+            let fetched = rx
+                .await
+                .map_err(|_| format!("fetch for locale {locale} was dropped"))?;
+            info!("checking quests for locale: {locale}");
 
-        info!("checking quests for locale: {locale}");
-
-        let quests = client
-            .fetch_quests_with_locale(token, locale)
-            .await
-            .map_err(|e| format!("failed to fetch quests for locale {locale}: {e}"))?;
-
-        let filtered = storage::filter_quests(&quests, reward_filter);
-        let filtered_len = filtered.len();
-
-        let filtered_prefixed: Vec<_> = filtered
-            .into_iter()
-            .map(|mut sq| {
-                sq.id = format!("{}:{}", locale, sq.id);
-                sq
-            })
-            .collect();
-
-        let seen_base: HashSet<String> = stored
-            .iter()
-            .map(|q| {
-                q.id.split(':')
-                    .next_back()
-                    .unwrap_or(q.id.as_str())
-                    .to_string()
-            })
-            .collect();
-
-        let mut new_for_locale = if seed_only {
-            Vec::new()
-        } else {
-            storage::find_new_quests(&filtered_prefixed, &stored)
-        };
-
-        new_for_locale.retain(|q| {
-            let base =
-                q.id.split(':')
-                    .next_back()
-                    .unwrap_or(q.id.as_str())
-                    .to_string();
-            !seen_base.contains(&base)
-        });
-
-        info!(
-            "fetched {} quests, filtered to {} (locale: {}, filter={})",
-            quests.len(),
-            filtered_len,
-            locale,
-            reward_filter
-        );
+            // a failed locale is skipped for this cycle; its stored quests are
+            // left alone so nothing is marked as ended on a bad fetch
+            let (fetched, account) = match fetched {
+                Ok(fetched) => fetched,
+                Err(e) => {
+                    error!("failed to fetch quests for locale {locale}: {e}");
+                    let outcome = LocaleOutcome::from_error(&e);
+                    checkpoint.record(locale, &outcome, Utc::now());
+                    summary.record(locale, outcome);
+                    continue;
+                }
+            };
+            let outcome = LocaleOutcome::Fetched {
+                quests: fetched.quests.len(),
+            };
+            let quests = fetched.quests;
+            for bad in &fetched.undecoded {
+                debug!(
+                    "undecoded quest {} for locale {locale}: {}",
+                    bad.id.as_deref().unwrap_or("(no id)"),
+                    bad.raw
+                );
+            }
 
-        if new_for_locale.is_empty() {
-            debug!("no new quests for locale {locale}");
-        } else {
-            info!(
-                "found {} new quests for locale {locale}",
-                new_for_locale.len()
-            );
+            let filtered = storage::filter_quests(&quests, reward_filter);
+            let filtered_len = filtered.len();
 
-            let new_ids: Vec<String> = new_for_locale
+            let filtered_prefixed: Vec<_> = filtered
+                .into_iter()
+                .map(|mut sq| {
+                    sq.id = format!("{}:{}", locale, sq.id);
+                    sq.seen_by = Some(account.clone());
+                    sq
+                })
+                .collect();
+
+            let excluded_prefixed: Vec<StoredQuest> = fetched
+                .excluded
                 .iter()
-                .map(|q| {
-                    q.id.split(':')
-                        .next_back()
-                        .unwrap_or(q.id.as_str())
-                        .to_string()
+                .map(|eq| {
+                    let mut sq = StoredQuest::from(eq);
+                    sq.id = format!("{}:{}", locale, sq.id);
+                    sq.seen_by = Some(account.clone());
+                    sq
                 })
                 .collect();
-            let full_new_quests: Vec<_> = quests
+
+            info!(
+                "fetched {} quests, filtered to {} (locale: {}, filter={})",
+                quests.len(),
+                filtered_len,
+                locale,
+                reward_filter
+            );
+
+            let present_ids: HashSet<String> = quests
                 .iter()
-                .filter(|q| new_ids.contains(&q.config.id))
-                .cloned()
+                .map(|q| q.config.id.clone())
+                .chain(
+                    fetched
+                        .excluded
+                        .iter()
+                        .map(|eq| format!("{EXCLUDED_ID_PREFIX}{}", eq.id)),
+                )
                 .collect();
+            let save_err = |e| format!("failed to save quests after locale {locale}: {e}");
 
-            for notifier in notifiers {
-                notifier.notify_full(&full_new_quests).await.map_err(|e| {
-                    format!("failed to send notifications for locale {locale}: {e}")
-                })?;
+            // work out what changed and save it under the store lock; webhooks
+            // are only called once it is released, so a slow or retried send
+            // doesn't hold up ingests and the lifecycle scheduler
+            let (ended, updates, full_excluded) = {
+                let store = store_handle.lock().await;
+                stored = store.load().map_err(load_err)?;
+
+                let (removed_excluded, removed): (Vec<_>, Vec<_>) = storage::find_removed_quests(
+                    &store.by_region(locale).map_err(load_err)?,
+                    locale,
+                    &present_ids,
+                    Utc::now(),
+                )
+                .into_iter()
+                .partition(|q| q.excluded);
+                if !removed_excluded.is_empty() {
+                    // no longer excluded is not the same as ended; if the quest became
+                    // available it shows up as a new quest instead
+                    let gone: HashSet<&str> =
+                        removed_excluded.iter().map(|q| q.id.as_str()).collect();
+                    let pruned = store
+                        .prune(&|q| !gone.contains(q.id.as_str()))
+                        .map_err(save_err)?;
+                    debug!("dropped {pruned} excluded quest entries no longer listed for {locale}");
+                    stored = store.load().map_err(load_err)?;
+                }
+                // announce once per quest, not once per locale it vanished from
+                let ended = storage::ended_to_announce(&*store, &removed).map_err(load_err)?;
+                if !removed.is_empty() {
+                    for quest in &removed {
+                        let reason = quest.ended.as_ref().map(|e| e.reason.label());
+                        info!(
+                            "quest {} ({}) is gone from locale {locale}: {}",
+                            quest.id,
+                            quest.name,
+                            reason.unwrap_or("ended")
+                        );
+                    }
+
+                    store.mark_ended(&removed).map_err(save_err)?;
+                    stored = store.load().map_err(load_err)?;
+                }
+
+                let seen_base: HashSet<String> =
+                    stored.iter().map(|q| q.base_id().to_string()).collect();
+
+                let new_for_locale = if seed_only {
+                    Vec::new()
+                } else {
+                    store.find_new(&filtered_prefixed).map_err(load_err)?
+                };
+
+                if new_for_locale.is_empty() {
+                    debug!("no new quests for locale {locale}");
+                }
+                for entry in new_for_locale {
+                    if seen_base.contains(entry.base_id()) {
+                        info!(
+                            "known quest {} ({}) is now also listed for locale {locale}",
+                            entry.base_id(),
+                            entry.name
+                        );
+                        new_regions.push(entry);
+                        continue;
+                    }
+                    info!(
+                        "quest {} ({}) seen by account {}",
+                        entry.id, entry.name, account
+                    );
+                    if let Some(quest) = quests.iter().find(|q| q.config.id == entry.base_id()) {
+                        // announce with the en-US text when that locale lists it too
+                        match new_quests
+                            .iter_mut()
+                            .find(|q| q.config.id == quest.config.id)
+                        {
+                            Some(known) if locale == "en-US" => *known = quest.clone(),
+                            Some(_) => {}
+                            None => new_quests.push(quest.clone()),
+                        }
+                    }
+                    new_entries.push(entry);
+                }
+
+                let updates = if seed_only {
+                    Vec::new()
+                } else {
+                    storage::find_updated_quests(&filtered_prefixed, &stored)
+                };
+                let refreshed = storage::refresh_known_quests(&mut stored, &filtered_prefixed);
+                store.upsert(&refreshed).map_err(save_err)?;
+
+                let mut new_excluded = if seed_only {
+                    Vec::new()
+                } else {
+                    store.find_new(&excluded_prefixed).map_err(load_err)?
+                };
+                new_excluded.retain(|q| !seen_base.contains(q.base_id()));
+
+                let mut full_excluded = Vec::new();
+                if !new_excluded.is_empty() {
+                    info!(
+                        "found {} new excluded quests for locale {locale}",
+                        new_excluded.len()
+                    );
+                    let new_excluded_ids: Vec<String> = new_excluded
+                        .iter()
+                        .map(|q| q.quest_id().to_string())
+                        .collect();
+                    full_excluded = fetched
+                        .excluded
+                        .iter()
+                        .filter(|eq| new_excluded_ids.contains(&eq.id))
+                        .cloned()
+                        .collect();
+
+                    store.upsert(&new_excluded).map_err(save_err)?;
+                    stored = store.load().map_err(load_err)?;
+                }
+
+                (ended, updates, full_excluded)
+            };
+            // only now is the locale's fetch saved, so a cycle cut off before
+            // this point fetches it first again on restart
+            checkpoint.record(locale, &outcome, Utc::now());
+            summary.record(locale, outcome);
+
+            all_filtered_quests.extend(filtered_prefixed);
+            all_filtered_quests.extend(excluded_prefixed);
+
+            if !seed_only {
+                for notifier in notifiers {
+                    if let Err(e) = notifier.notify_ended(&ended, locale).await {
+                        warn!("failed to send ended notification for locale {locale}: {e}");
+                    }
+                }
+
+                let retried = delivery::retry_due(
+                    notifiers,
+                    &quests,
+                    locale,
+                    &stored,
+                    &storage::availability(&stored, locales),
+                    Utc::now(),
+                )
+                .await;
+                store_handle
+                    .lock()
+                    .await
+                    .record_deliveries(&retried)
+                    .map_err(save_err)?;
             }
 
-            stored.extend(new_for_locale.iter().cloned());
-            let merged_local_stored = dedupe_by_key(&stored, |q| q.id.clone());
-            storage::save_quests(&merged_local_stored)
-                .map_err(|e| format!("failed to save quests after locale {locale}: {e}"))?;
-        }
+            if let Some(tracker) = progress {
+                if let Err(e) = tracker.track(&quests, &account).await {
+                    warn!("{e}");
+                }
+            }
 
-        all_filtered_quests.extend(filtered_prefixed);
-    }
+            if !updates.is_empty() {
+                info!("found {} updated quests for locale {locale}", updates.len());
+                for notifier in notifiers {
+                    if let Err(e) = notifier.notify_updates(&updates).await {
+                        warn!("failed to send update notification for locale {locale}: {e}");
+                    }
+                }
+            }
 
-    let stored = storage::load_stored_quests();
-    let new_quests = storage::find_new_quests(&all_filtered_quests, &stored);
+            if !full_excluded.is_empty() {
+                for notifier in notifiers {
+                    if let Err(e) = notifier.notify_excluded(&full_excluded, locale).await {
+                        warn!("failed to send excluded notification for locale {locale}: {e}");
+                    }
+                }
+            }
+        }
+        Ok::<(), String>(())
+    };
+    let (_, handled) = tokio::join!(fetch_all, handle_all);
+    handled?;
+
+    let stored = store_handle.lock().await.load().map_err(load_err)?;
     info!("found {} new quests across all locales", new_quests.len());
 
     if seed_only {
         info!("initial run detected and initial_send_all=false; skipping notifications and seeding storage");
-    } else if !new_quests.is_empty() {
-        let all_quests = client
-            .fetch_quests_with_locale(token, "en-US")
-            .await
-            .map_err(|e| format!("failed to fetch quests for notifications: {e}"))?;
-
-        let new_quest_ids: Vec<String> = new_quests
-            .iter()
-            .map(|q| {
-                q.id.split(':')
-                    .next_back()
-                    .unwrap_or(q.id.as_str())
-                    .to_string()
-            })
-            .collect();
-        let full_new_quests: Vec<_> = all_quests
-            .iter()
-            .filter(|q| new_quest_ids.contains(&q.config.id))
-            .cloned()
-            .collect();
-
-        for notifier in notifiers {
-            notifier
-                .notify_full(&full_new_quests)
-                .await
-                .map_err(|e| format!("failed to send notifications: {e}"))?;
+    } else {
+        let availability = storage::availability(
+            stored.iter().chain(&new_entries).chain(&new_regions),
+            locales,
+        );
+        delivery::announce_new(
+            notifiers,
+            &new_quests,
+            &mut new_entries,
+            &availability,
+            Utc::now(),
+        )
+        .await;
+
+        let region_updates = storage::find_region_updates(&new_regions, &availability);
+        if !region_updates.is_empty() {
+            info!(
+                "{} known quests showed up in new locales",
+                region_updates.len()
+            );
+            for notifier in notifiers {
+                if let Err(e) = notifier.notify_updates(&region_updates).await {
+                    warn!("failed to send region update notification: {e}");
+                }
+            }
         }
     }
 
-    let merged_quests = dedupe_by_key(&all_filtered_quests, |q| q.id.clone());
-    storage::save_quests(&merged_quests).map_err(|e| format!("failed to save quests: {e}"))?;
+    let decode_failures = source.decode_failures();
+    if decode_failures > 0 {
+        warn!("{decode_failures} quests failed to decode since startup; the quest schema may have changed");
+    }
+
+    let store = store_handle.lock().await;
+    let stored = store.load().map_err(load_err)?;
+    let mut merged_quests = dedupe_by_key(&all_filtered_quests, |q| q.id.clone());
+    scheduler::carry_states(&mut merged_quests, &stored);
+    scheduler::carry_states(&mut merged_quests, &new_entries);
+    store
+        .upsert(&merged_quests)
+        .map_err(|e| format!("failed to save quests: {e}"))?;
 
-    Ok(())
+    Ok(summary)
 }
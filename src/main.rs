@@ -1,16 +1,26 @@
 mod agents;
+mod cli;
 mod collector;
 mod models;
+mod replay;
+mod scheduler;
 mod services;
 mod utils;
 
+use cli::Command;
 use log::{debug, error, info};
-use models::{AppError, Config, LOCALES};
-use services::{storage, QuestClient};
+use models::{AppError, Config};
+use services::{
+    accounts::AccountPool,
+    storage::{self, StoreHandle},
+    QuestClient, RetryPolicy,
+};
 use std::time::Duration;
 
 type AppInit = (
     QuestClient,
+    AccountPool,
+    StoreHandle,
     Vec<services::webhook::WebhookNotifier>,
     Vec<String>,
 );
@@ -21,6 +31,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .filter_level(log::LevelFilter::Info)
         .init();
 
+    let command = Command::from_args().map_err(|e| {
+        eprintln!("{e}");
+        Box::<dyn std::error::Error>::from(AppError(e))
+    })?;
+
     let config = Config::load()
         .map_err(|e| {
             eprintln!("Failed to load configuration: {e}");
@@ -28,19 +43,131 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         })
         .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
 
-    let (client, notifiers, locales_to_check) = init_app(&config)?;
+    match command {
+        Command::Run => {}
+        Command::SuperProperties => {
+            print_super_properties(&config);
+            return Ok(());
+        }
+        Command::Replay { dir } => {
+            let store = storage::open_store("memory", config.storage_path())?;
+            let mut notifiers: Vec<_> = build_notifiers(&config)
+                .into_iter()
+                .map(|n| n.with_dry_run(true))
+                .collect();
+            if notifiers.is_empty() {
+                notifiers.push(
+                    services::webhook::WebhookNotifier::new(
+                        String::new(),
+                        Some("replay".to_string()),
+                        None,
+                    )
+                    .with_dry_run(true),
+                );
+            }
+            let dir = dir.as_deref().unwrap_or_else(|| config.archive_dir());
+            return replay::replay_archive(
+                dir,
+                &store,
+                &notifiers,
+                config.reward_filter(),
+                config.initial_send_all(),
+            )
+            .await
+            .map_err(|e| Box::<dyn std::error::Error>::from(AppError(e)));
+        }
+        Command::Import { path } => {
+            if config.storage_type() != "sqlite" {
+                return Err(Box::<dyn std::error::Error>::from(AppError(
+                    "import needs storage_type = \"sqlite\" in [notifier]".to_string(),
+                )));
+            }
+            let store = storage::open_store(config.storage_type(), config.storage_path())?;
+            storage::import_json(&*store.lock().await, &path)?;
+            return Ok(());
+        }
+        Command::Locales => {
+            print_locale_fetches(&config)?;
+            return Ok(());
+        }
+        Command::Prune => {
+            let store = storage::open_store(config.storage_type(), config.storage_path())?;
+            let pruned = services::retention::RetentionPolicy::from_config(&config)
+                .apply(&*store.lock().await, chrono::Utc::now())?;
+            println!("pruned {} stored quests", pruned.len());
+            return Ok(());
+        }
+    }
+
+    let (client, accounts, store, notifiers, locales_to_check) = init_app(&config)?;
+
+    if config.startup_check() {
+        let report =
+            services::diagnostics::run_startup_checks(&client, &accounts, &notifiers).await;
+        report.log();
+        if !report.is_healthy() && config.startup_check_strict() {
+            return Err(Box::<dyn std::error::Error>::from(AppError(
+                "startup validation failed and startup_check_strict=true; refusing to start"
+                    .to_string(),
+            )));
+        }
+    }
+
+    let progress = config.progress_webhook().map(|url| {
+        info!("personal quest progress tracking enabled");
+        services::progress::ProgressTracker::new(
+            config.progress_state_path(),
+            config.progress_warn_before_hours(),
+            services::webhook::WebhookNotifier::new(
+                url.to_string(),
+                Some("progress".to_string()),
+                None,
+            ),
+        )
+    });
+
+    let retention = config.retention_enabled().then(|| {
+        info!(
+            "pruning stored quests {} days after they finish",
+            config.retention_prune_after_days()
+        );
+        services::retention::RetentionPolicy::from_config(&config)
+    });
+
+    let admin = config.admin_webhook().map(|url| {
+        services::webhook::WebhookNotifier::new(url.to_string(), Some("admin".to_string()), None)
+    });
 
     info!(
-        "role={}, using locale mode: {} (will check {} locale(s))",
+        "role={}, will check {} locale(s): {}",
         config.role(),
-        config.locale_mode(),
-        locales_to_check.len()
+        locales_to_check.len(),
+        locales_to_check.join(", ")
+    );
+
+    let checkpoint = services::checkpoint::CycleCheckpoint::open(
+        config.cycle_state_path(),
+        locales_to_check.clone(),
     );
 
     loop {
         if config.is_agent() {
-            match agents::agent_cycle(&client, &config, &locales_to_check).await {
+            // the guard must be gone before retention locks the store again
+            let outcome = {
+                let guard = store.lock().await;
+                agents::agent_cycle(
+                    &client,
+                    &accounts,
+                    &*guard,
+                    &config,
+                    &locales_to_check,
+                    progress.as_ref(),
+                )
+                .await
+            };
+            match outcome {
                 Ok(()) => {
+                    apply_retention(retention.as_ref(), &store).await;
                     if config.run_once() {
                         break;
                     }
@@ -53,18 +180,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
         } else {
+            let source = services::source::QuestSource::Live {
+                client: &client,
+                accounts: &accounts,
+            };
             match collector::check_quests_all_locales(
-                &client,
+                &source,
+                &store,
                 &notifiers,
-                &config.discord.token,
                 config.reward_filter(),
-                &locales_to_check,
+                &checkpoint,
                 config.initial_send_all(),
+                progress.as_ref(),
             )
             .await
             {
-                Ok(()) => {
+                Ok(summary) => {
+                    report_cycle(&summary, admin.as_ref()).await;
+                    apply_retention(retention.as_ref(), &store).await;
                     if config.run_once() {
+                        if summary.all_failed() {
+                            return Err(Box::<dyn std::error::Error>::from(AppError(
+                                "no locale could be fetched".to_string(),
+                            )));
+                        }
                         info!("RUN_ONCE mode: exiting after first check");
                         break;
                     }
@@ -80,7 +219,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         if !config.run_once() {
             info!("next check in {} minutes", config.fetch_interval());
-            tokio::time::sleep(Duration::from_secs(config.fetch_interval() * 60)).await;
+            let interval = Duration::from_secs(config.fetch_interval() * 60);
+            if !config.is_agent() {
+                scheduler::wait_for_next_check(
+                    &store,
+                    interval,
+                    &notifiers,
+                    config.announce_live(),
+                )
+                .await;
+            } else {
+                tokio::time::sleep(interval).await;
+            }
         }
     }
     Ok(())
@@ -94,13 +244,21 @@ fn init_app(config: &Config) -> Result<AppInit, Box<dyn std::error::Error>> {
         .and_then(|w| w.first())
         .map(|entry| entry.url.clone());
 
+    let accounts = AccountPool::from_config(config);
+    if accounts.is_empty() {
+        return Err(Box::<dyn std::error::Error>::from(AppError(
+            "No Discord accounts configured".to_string(),
+        )));
+    }
+
     debug!(
-        "Config loaded - Token: {}..., Webhooks: {}...",
-        &config.discord.token[..20.min(config.discord.token.len())],
+        "Config loaded - Accounts: {}, Webhooks: {}...",
+        accounts.len(),
         &first_webhook.unwrap_or_else(|| "(none)".to_string())
     );
+    accounts.log_summary();
 
-    storage::init_storage(config.storage_type(), config.storage_path());
+    let store = storage::open_store(config.storage_type(), config.storage_path())?;
 
     info!(
         "starting Discord Quest Notifier - filter={}, interval={} min, run_once={}, storage_type={}",
@@ -110,31 +268,38 @@ fn init_app(config: &Config) -> Result<AppInit, Box<dyn std::error::Error>> {
         config.storage_type()
     );
 
-    let client = QuestClient::new(config.super_properties().to_string());
-
-    // support multiple webhooks defined under [discord] as [[discord.webhooks]] = [{ name = "x", url = "..." }, ...]
-    let notifiers: Vec<services::webhook::WebhookNotifier> = match &config.discord.webhooks {
-        Some(entries) if !entries.is_empty() => entries
-            .iter()
-            .map(|entry| {
-                services::webhook::WebhookNotifier::new(
-                    entry.url.clone(),
-                    entry.name.clone(),
-                    entry.message.clone(),
-                )
-            })
-            .collect(),
-        _ => {
-            if config.is_agent() {
-                // agent mode does not require webhooks
-                Vec::new()
-            } else {
-                return Err(Box::<dyn std::error::Error>::from(AppError(
-                    "No webhooks configured. Please add [[discord.webhooks]] entries in config.toml"
-                        .to_string(),
-                )));
-            }
-        }
+    let client = QuestClient::new(config.api_url())
+        .with_localized_super_properties(config.localize_super_properties())
+        .with_request_budget(services::budget::RequestBudget::per_minute(
+            config.requests_per_minute(),
+        ))
+        .with_retry_policy(RetryPolicy {
+            max_retries: config.max_retries(),
+            max_rate_limit_wait: Duration::from_secs(config.max_retry_wait_secs()),
+            ..RetryPolicy::default()
+        })
+        .with_proxies(config.proxies())?
+        .with_archive(config.archive_enabled().then(|| {
+            services::archive::ResponseArchive::new(
+                config.archive_dir(),
+                config.archive_max_files(),
+            )
+        }));
+    info!("using Discord API at {}", config.api_url());
+
+    let notifiers = build_notifiers(config);
+    if notifiers.is_empty() && !config.is_agent() {
+        // agent mode does not require webhooks
+        return Err(Box::<dyn std::error::Error>::from(AppError(
+            "No webhooks configured. Please add [[discord.webhooks]] entries in config.toml"
+                .to_string(),
+        )));
+    }
+
+    let locales_to_check: Vec<String> = if config.is_agent() {
+        vec![config.region_code().to_string()]
+    } else {
+        config.collector_locales()?
     };
 
     // If collector, start ingest server concurrently
@@ -142,8 +307,11 @@ fn init_app(config: &Config) -> Result<AppInit, Box<dyn std::error::Error>> {
         let accept = config.accept_token().map(ToString::to_string);
         let port = config.ingest_port();
         let notifiers_clone = notifiers.clone();
+        let store_clone = store.clone();
+        let locales = locales_to_check.clone();
         tokio::spawn(async move {
-            services::ingest::start_server(accept, port, notifiers_clone).await;
+            services::ingest::start_server(accept, port, notifiers_clone, store_clone, locales)
+                .await;
         });
     }
 
@@ -161,13 +329,91 @@ fn init_app(config: &Config) -> Result<AppInit, Box<dyn std::error::Error>> {
         );
     }
 
-    let locales_to_check: Vec<String> = if config.is_agent() {
-        vec![config.region_code().to_string()]
-    } else if config.locale_mode() == "all" {
-        LOCALES.iter().map(|s| (*s).to_string()).collect()
-    } else {
-        vec![config.region_code().to_string()]
-    };
+    Ok((client, accounts, store, notifiers, locales_to_check))
+}
 
-    Ok((client, notifiers, locales_to_check))
+/// Log which locales failed in a cycle and post them to the admin webhook.
+async fn report_cycle(
+    summary: &models::CycleSummary,
+    admin: Option<&services::webhook::WebhookNotifier>,
+) {
+    summary.log();
+    if let Some(admin) = admin {
+        if let Err(e) = admin.notify_cycle_summary(summary).await {
+            error!("failed to post cycle summary to the admin webhook: {e}");
+        }
+    }
+}
+
+async fn apply_retention(
+    policy: Option<&services::retention::RetentionPolicy>,
+    store: &StoreHandle,
+) {
+    if let Some(policy) = policy {
+        if let Err(e) = policy.apply(&*store.lock().await, chrono::Utc::now()) {
+            error!("failed to prune stored quests: {e}");
+        }
+    }
+}
+
+fn print_locale_fetches(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let checkpoint = services::checkpoint::CycleCheckpoint::open(
+        config.cycle_state_path(),
+        config.collector_locales()?,
+    );
+    for (locale, fetch) in checkpoint.fetches() {
+        match fetch {
+            Some(fetch) => println!(
+                "{locale:<8} last fetched {}  (last attempt {}: {})",
+                fetch.fetched_at.as_deref().unwrap_or("never"),
+                fetch.attempted_at,
+                fetch.outcome
+            ),
+            None => println!("{locale:<8} never fetched"),
+        }
+    }
+    Ok(())
+}
+
+fn print_super_properties(config: &Config) {
+    let accounts = AccountPool::from_config(config);
+    for account in accounts.iter() {
+        println!("# account: {}", account.name);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(account.super_properties.decoded()).unwrap_or_default()
+        );
+        println!("X-Super-Properties: {}", account.super_properties.encoded());
+        if config.localize_super_properties() {
+            println!("(system_locale is replaced with the fetched locale on each request)");
+        }
+        println!();
+    }
+}
+
+// support multiple webhooks defined under [discord] as [[discord.webhooks]] = [{ name = "x", url = "..." }, ...]
+fn build_notifiers(config: &Config) -> Vec<services::webhook::WebhookNotifier> {
+    config
+        .discord
+        .webhooks
+        .iter()
+        .flatten()
+        .map(|entry| {
+            services::webhook::WebhookNotifier::new(
+                entry.url.clone(),
+                entry.name.clone(),
+                entry.message.clone(),
+            )
+            .with_excluded_notifications(entry.notify_excluded.unwrap_or(false))
+            .with_update_notifications(entry.notify_updates.unwrap_or(true))
+            .with_ended_notifications(entry.notify_ended.unwrap_or(false))
+            .with_delivery_policy(services::delivery::DeliveryPolicy {
+                max_attempts: config.delivery_max_attempts(),
+                // checked when the config was loaded
+                base_delay: models::minutes_to_duration(config.delivery_retry_minutes())
+                    .unwrap_or(chrono::Duration::MAX),
+                ..services::delivery::DeliveryPolicy::default()
+            })
+        })
+        .collect()
 }
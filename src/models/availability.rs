@@ -0,0 +1,31 @@
+use std::collections::BTreeSet;
+
+/// Where a quest is listed, as shown on its announcement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Availability {
+    /// Listed in every locale the collector checks
+    Global,
+    Regions(Vec<String>),
+}
+
+impl Availability {
+    /// Availability of a quest listed in `regions`. It is global when more
+    /// than one locale is checked and every one of them lists it.
+    #[must_use]
+    pub fn new(regions: &BTreeSet<String>, checked: &[String]) -> Self {
+        if checked.len() > 1 && checked.iter().all(|l| regions.contains(l)) {
+            Self::Global
+        } else {
+            Self::Regions(regions.iter().cloned().collect())
+        }
+    }
+
+    /// Value of the "Available in" line, e.g. "ko-KR, ja-JP".
+    #[must_use]
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Global => "🌍 Global".to_string(),
+            Self::Regions(regions) => regions.join(", "),
+        }
+    }
+}
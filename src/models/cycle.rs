@@ -0,0 +1,110 @@
+use crate::models::ClientError;
+use log::{info, warn};
+use std::time::Duration;
+
+/// How fetching one locale went during a collector cycle.
+#[derive(Debug, Clone)]
+pub enum LocaleOutcome {
+    Fetched {
+        quests: usize,
+    },
+    /// Discord asked us to back off for longer than the retry policy allows
+    RateLimited {
+        retry_after: Option<Duration>,
+    },
+    /// The response was not a quests response
+    DecodeError(String),
+    /// Every account for the locale was rejected
+    AuthError(String),
+    /// Network errors, server errors and anything else
+    Failed(String),
+}
+
+impl LocaleOutcome {
+    #[must_use]
+    pub fn from_error(error: &ClientError) -> Self {
+        match error {
+            ClientError::RateLimited { .. } => Self::RateLimited {
+                retry_after: error.retry_after(),
+            },
+            ClientError::RetriesExhausted { last, .. }
+                if matches!(**last, ClientError::RateLimited { .. }) =>
+            {
+                Self::RateLimited { retry_after: None }
+            }
+            ClientError::Decode(_) => Self::DecodeError(error.to_string()),
+            ClientError::HttpError(401 | 403) | ClientError::NoActiveAccount(_) => {
+                Self::AuthError(error.to_string())
+            }
+            _ => Self::Failed(error.to_string()),
+        }
+    }
+
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Fetched { .. })
+    }
+
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Fetched { .. } => "ok",
+            Self::RateLimited { .. } => "rate limited",
+            Self::DecodeError(_) => "decode error",
+            Self::AuthError(_) => "auth error",
+            Self::Failed(_) => "failed",
+        }
+    }
+
+    #[must_use]
+    pub fn detail(&self) -> String {
+        match self {
+            Self::Fetched { quests } => format!("{quests} quests"),
+            Self::RateLimited {
+                retry_after: Some(wait),
+            } => format!("retry in {}s", wait.as_secs()),
+            Self::RateLimited { retry_after: None } => "retries exhausted".to_string(),
+            Self::DecodeError(e) | Self::AuthError(e) | Self::Failed(e) => e.clone(),
+        }
+    }
+}
+
+/// Per-locale outcomes of one collector cycle, in the order the locales were
+/// handled.
+#[derive(Debug, Clone, Default)]
+pub struct CycleSummary {
+    pub outcomes: Vec<(String, LocaleOutcome)>,
+}
+
+impl CycleSummary {
+    pub fn record(&mut self, locale: &str, outcome: LocaleOutcome) {
+        self.outcomes.push((locale.to_string(), outcome));
+    }
+
+    /// Locales whose fetch failed, with what went wrong.
+    pub fn failed(&self) -> impl Iterator<Item = &(String, LocaleOutcome)> {
+        self.outcomes.iter().filter(|(_, o)| !o.is_success())
+    }
+
+    /// True if locales were checked and not one of them could be fetched.
+    #[must_use]
+    pub fn all_failed(&self) -> bool {
+        !self.outcomes.is_empty() && self.outcomes.iter().all(|(_, o)| !o.is_success())
+    }
+
+    pub fn log(&self) {
+        let failed = self.failed().count();
+        info!(
+            "cycle finished: {} of {} locales fetched",
+            self.outcomes.len() - failed,
+            self.outcomes.len()
+        );
+        for (locale, outcome) in self.failed() {
+            warn!(
+                "locale {locale}: {} ({})",
+                outcome.label(),
+                outcome.detail()
+            );
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use crate::models::{Availability, StoredQuest, FINGERPRINT_PREFIX};
+use crate::utils::parse_datetime;
+
+/// A known quest whose config changed, in its new version.
+#[derive(Debug, Clone)]
+pub struct QuestUpdate {
+    pub quest: StoredQuest,
+    pub changes: Vec<QuestChange>,
+}
+
+/// One field-level difference between two versions of the same quest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuestChange {
+    Name {
+        from: String,
+        to: String,
+    },
+    StartsAt {
+        from: String,
+        to: String,
+    },
+    ExpiresAt {
+        from: String,
+        to: String,
+    },
+    RewardsExpireAt {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    Rewards {
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    Tasks {
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    ConfigVersion {
+        from: u32,
+        to: u32,
+    },
+    /// The quest showed up in regions it wasn't listed in before
+    Regions {
+        added: Vec<String>,
+        now: Availability,
+    },
+    /// The config hash changed but none of the fields above did
+    Other,
+}
+
+impl QuestChange {
+    /// Human readable description, e.g. "expires_at extended by 7 days".
+    #[must_use]
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Name { from, to } => format!("name changed from \"{from}\" to \"{to}\""),
+            Self::StartsAt { from, to } => describe_shift("starts_at", from, to),
+            Self::ExpiresAt { from, to } => describe_shift("expires_at", from, to),
+            Self::RewardsExpireAt { from, to } => match (from, to) {
+                (Some(from), Some(to)) => describe_shift("rewards_expire_at", from, to),
+                (None, Some(to)) => format!("rewards_expire_at set to {to}"),
+                (Some(_), None) => "rewards_expire_at removed".to_string(),
+                (None, None) => "rewards_expire_at changed".to_string(),
+            },
+            Self::Rewards { from, to } => {
+                format!("reward changed: {} → {}", list(from), list(to))
+            }
+            Self::Tasks { from, to } => format!("tasks changed: {} → {}", list(from), list(to)),
+            Self::ConfigVersion { from, to } => format!("config_version {from} → {to}"),
+            Self::Regions { added, now } => match now {
+                Availability::Global => {
+                    format!(
+                        "now also available in {}; listed globally",
+                        added.join(", ")
+                    )
+                }
+                Availability::Regions(_) => format!(
+                    "now also available in {} (listed in {})",
+                    added.join(", "),
+                    now.describe()
+                ),
+            },
+            Self::Other => "quest config changed".to_string(),
+        }
+    }
+}
+
+impl StoredQuest {
+    /// Differences from `old` to `self`. Empty if the fingerprints match,
+    /// either side has none (entries stored before fingerprints existed) or
+    /// they were built by different fingerprint schemes.
+    #[must_use]
+    pub fn changes_since(&self, old: &Self) -> Vec<QuestChange> {
+        match (&old.fingerprint, &self.fingerprint) {
+            (Some(a), Some(b))
+                if a != b
+                    && a.starts_with(FINGERPRINT_PREFIX) == b.starts_with(FINGERPRINT_PREFIX) => {}
+            _ => return Vec::new(),
+        }
+
+        let mut changes = Vec::new();
+        if old.name != self.name {
+            changes.push(QuestChange::Name {
+                from: old.name.clone(),
+                to: self.name.clone(),
+            });
+        }
+        if old.starts_at != self.starts_at {
+            changes.push(QuestChange::StartsAt {
+                from: old.starts_at.clone(),
+                to: self.starts_at.clone(),
+            });
+        }
+        if old.expires_at != self.expires_at {
+            changes.push(QuestChange::ExpiresAt {
+                from: old.expires_at.clone(),
+                to: self.expires_at.clone(),
+            });
+        }
+        if old.rewards_expire_at != self.rewards_expire_at {
+            changes.push(QuestChange::RewardsExpireAt {
+                from: old.rewards_expire_at.clone(),
+                to: self.rewards_expire_at.clone(),
+            });
+        }
+        let (old_rewards, new_rewards) = (reward_labels(old), reward_labels(self));
+        if old_rewards != new_rewards {
+            changes.push(QuestChange::Rewards {
+                from: old_rewards,
+                to: new_rewards,
+            });
+        }
+        if old.tasks != self.tasks {
+            changes.push(QuestChange::Tasks {
+                from: old.tasks.clone(),
+                to: self.tasks.clone(),
+            });
+        }
+        if changes.is_empty() {
+            changes.push(QuestChange::Other);
+        }
+        if let (Some(from), Some(to)) = (old.config_version, self.config_version) {
+            if from != to {
+                changes.push(QuestChange::ConfigVersion { from, to });
+            }
+        }
+        changes
+    }
+}
+
+fn reward_labels(quest: &StoredQuest) -> Vec<String> {
+    quest
+        .rewards
+        .iter()
+        .map(|r| match r.orb_quantity {
+            Some(orbs) => format!("{} ({orbs})", r.name),
+            None => r.name.clone(),
+        })
+        .collect()
+}
+
+fn list(items: &[String]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
+/// "expires_at extended by 7 days", or the raw values if either side doesn't parse.
+fn describe_shift(field: &str, from: &str, to: &str) -> String {
+    let (Some(from_dt), Some(to_dt)) = (parse_datetime(from), parse_datetime(to)) else {
+        return format!("{field} changed from {from} to {to}");
+    };
+    let delta = to_dt - from_dt;
+    let verb = if delta.num_seconds() >= 0 {
+        "extended"
+    } else {
+        "brought forward"
+    };
+    let secs = delta.num_seconds().unsigned_abs();
+    let amount = match secs {
+        s if s >= 86_400 => plural(s / 86_400, "day"),
+        s if s >= 3_600 => plural(s / 3_600, "hour"),
+        s => plural(s.div_ceil(60), "minute"),
+    };
+    format!("{field} {verb} by {amount}")
+}
+
+fn plural(n: u64, unit: &str) -> String {
+    if n == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{n} {unit}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(fingerprint: Option<&str>, expires_at: &str) -> StoredQuest {
+        serde_json::from_value(serde_json::json!({
+            "id": "en-US:1",
+            "name": "Quest",
+            "starts_at": "2026-01-01T00:00:00Z",
+            "expires_at": expires_at,
+            "game_name": "Game",
+            "fingerprint": fingerprint,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn no_changes_without_comparable_fingerprints() {
+        let old = stored(Some("v2:aaaa"), "2026-01-10T00:00:00Z");
+        let same = stored(Some("v2:aaaa"), "2026-01-17T00:00:00Z");
+        let legacy = stored(Some("bbbb"), "2026-01-10T00:00:00Z");
+        let unhashed = stored(None, "2026-01-17T00:00:00Z");
+        assert!(same.changes_since(&old).is_empty());
+        assert!(old.changes_since(&legacy).is_empty());
+        assert!(unhashed.changes_since(&old).is_empty());
+        assert!(old.changes_since(&unhashed).is_empty());
+    }
+
+    #[test]
+    fn reports_changed_fields() {
+        let old = stored(Some("v2:aaaa"), "2026-01-10T00:00:00Z");
+        let new = stored(Some("v2:bbbb"), "2026-01-17T00:00:00Z");
+        assert_eq!(
+            new.changes_since(&old),
+            vec![QuestChange::ExpiresAt {
+                from: "2026-01-10T00:00:00Z".to_string(),
+                to: "2026-01-17T00:00:00Z".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn a_new_hash_without_field_changes_is_other() {
+        let old = stored(Some("v2:aaaa"), "2026-01-10T00:00:00Z");
+        let new = stored(Some("v2:bbbb"), "2026-01-10T00:00:00Z");
+        assert_eq!(new.changes_since(&old), vec![QuestChange::Other]);
+    }
+
+    #[test]
+    fn describes_shifts_in_the_largest_whole_unit() {
+        let cases = [
+            ("2026-01-17T00:00:00Z", "expires_at extended by 7 days"),
+            ("2026-01-11T00:00:00Z", "expires_at extended by 1 day"),
+            ("2026-01-10T05:30:00Z", "expires_at extended by 5 hours"),
+            ("2026-01-10T00:00:30Z", "expires_at extended by 1 minute"),
+            (
+                "2026-01-09T22:00:00Z",
+                "expires_at brought forward by 2 hours",
+            ),
+            (
+                "soon",
+                "expires_at changed from 2026-01-10T00:00:00Z to soon",
+            ),
+        ];
+        for (to, expected) in cases {
+            assert_eq!(
+                describe_shift("expires_at", "2026-01-10T00:00:00Z", to),
+                expected
+            );
+        }
+    }
+}
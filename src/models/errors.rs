@@ -1,4 +1,5 @@
 use std::fmt;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct AppError(pub String);
@@ -17,10 +18,62 @@ pub enum ClientError {
     RequestFailed(#[from] reqwest::Error),
     #[error("HTTP error: {0}")]
     HttpError(u16),
+    #[error("failed to decode quests response: {0}")]
+    Decode(#[from] serde_json::Error),
+    #[error("no archived response for locale {0}")]
+    NotArchived(String),
+    #[error("rate limited, retry after {:.1}s", retry_after.as_secs_f64())]
+    RateLimited { retry_after: Duration },
+    #[error("server error: {0}")]
+    ServerError(u16),
+    #[error("no active account available for locale {0}")]
+    NoActiveAccount(String),
+    #[error("giving up after {attempts} attempts: {last}")]
+    RetriesExhausted {
+        attempts: u32,
+        last: Box<ClientError>,
+    },
+}
+
+impl ClientError {
+    /// How long Discord asked us to back off, if this is a rate limit that
+    /// the caller should retry later rather than give up on.
+    #[must_use]
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after } => Some(*retry_after),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum NotifyError {
     #[error("Failed to send notification: {0}")]
     SendFailed(#[from] reqwest::Error),
+    #[error("Webhook responded with status {0}")]
+    HttpStatus(u16),
+}
+
+impl NotifyError {
+    /// `true` when the webhook is known to be unusable (deleted or bad token)
+    /// rather than temporarily unreachable.
+    #[must_use]
+    pub fn is_definitive(&self) -> bool {
+        matches!(self, Self::HttpStatus(401 | 403 | 404))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("storage IO failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to encode or decode stored quests: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("stored quests file {path} is corrupt ({reason}); fix or restore it before starting again, it will not be treated as empty")]
+    Corrupt { path: String, reason: String },
+    #[error("unknown storage type: {0}")]
+    UnknownBackend(String),
 }
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// Outcome of announcing a new quest on one webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Delivered,
+    /// Failed, retried on a later cycle
+    Failed,
+    /// Gave up after too many attempts, or nothing left to retry
+    Dead,
+}
+
+/// Delivery state of a quest announcement on one webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    /// The webhook, see `WebhookNotifier::ledger_key`
+    pub webhook: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub last_attempt_at: String,
+    /// When a failed delivery is due for its next attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
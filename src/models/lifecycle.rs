@@ -0,0 +1,204 @@
+use crate::models::{Quest, StoredQuest};
+use crate::utils::parse_datetime;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Where a quest is in its lifetime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestLifecycle {
+    /// Before `starts_at`
+    Upcoming,
+    /// Between `starts_at` and `expires_at`
+    Live,
+    /// Past `expires_at`, but rewards can still be claimed
+    Expired,
+    /// Past `rewards_expire_at`
+    ClaimWindowClosed,
+}
+
+impl QuestLifecycle {
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Upcoming => "upcoming",
+            Self::Live => "live",
+            Self::Expired => "expired",
+            Self::ClaimWindowClosed => "claim window closed",
+        }
+    }
+}
+
+/// Why a quest disappeared from a locale's quest list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndReason {
+    /// Removed before `expires_at`
+    PulledEarly,
+    /// Removed at or after `expires_at`
+    Expired,
+}
+
+impl EndReason {
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::PulledEarly => "pulled early",
+            Self::Expired => "expired normally",
+        }
+    }
+}
+
+/// Recorded once a stored quest is no longer returned for its locale.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestEnd {
+    pub reason: EndReason,
+    /// When the removal was noticed (RFC 3339)
+    pub detected_at: String,
+}
+
+/// Parsed lifecycle boundaries; a missing or unparsable timestamp means the
+/// boundary is unknown and never crossed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LifecycleBounds {
+    pub starts_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub rewards_expire_at: Option<DateTime<Utc>>,
+}
+
+impl LifecycleBounds {
+    #[must_use]
+    pub fn parse(starts_at: &str, expires_at: &str, rewards_expire_at: Option<&str>) -> Self {
+        Self {
+            starts_at: parse_datetime(starts_at),
+            expires_at: parse_datetime(expires_at),
+            rewards_expire_at: rewards_expire_at.and_then(parse_datetime),
+        }
+    }
+
+    #[must_use]
+    pub fn state_at(&self, now: DateTime<Utc>) -> QuestLifecycle {
+        if self.rewards_expire_at.is_some_and(|t| now >= t) {
+            QuestLifecycle::ClaimWindowClosed
+        } else if self.expires_at.is_some_and(|t| now >= t) {
+            QuestLifecycle::Expired
+        } else if self.starts_at.is_some_and(|t| now < t) {
+            QuestLifecycle::Upcoming
+        } else {
+            QuestLifecycle::Live
+        }
+    }
+
+    /// The next boundary after `now`, with the state the quest enters there.
+    #[must_use]
+    pub fn next_transition(&self, now: DateTime<Utc>) -> Option<(DateTime<Utc>, QuestLifecycle)> {
+        [
+            (self.starts_at, QuestLifecycle::Live),
+            (self.expires_at, QuestLifecycle::Expired),
+            (self.rewards_expire_at, QuestLifecycle::ClaimWindowClosed),
+        ]
+        .into_iter()
+        .filter_map(|(at, state)| at.filter(|t| *t > now).map(|t| (t, state)))
+        .min_by_key(|(at, _)| *at)
+    }
+}
+
+impl Quest {
+    #[must_use]
+    pub fn lifecycle_bounds(&self) -> LifecycleBounds {
+        LifecycleBounds::parse(
+            &self.config.starts_at,
+            &self.config.expires_at,
+            self.config.rewards_config.rewards_expire_at.as_deref(),
+        )
+    }
+
+    #[must_use]
+    pub fn lifecycle(&self, now: DateTime<Utc>) -> QuestLifecycle {
+        self.lifecycle_bounds().state_at(now)
+    }
+}
+
+impl StoredQuest {
+    #[must_use]
+    pub fn lifecycle_bounds(&self) -> LifecycleBounds {
+        LifecycleBounds::parse(
+            &self.starts_at,
+            &self.expires_at,
+            self.rewards_expire_at.as_deref(),
+        )
+    }
+
+    #[must_use]
+    pub fn lifecycle(&self, now: DateTime<Utc>) -> QuestLifecycle {
+        self.lifecycle_bounds().state_at(now)
+    }
+
+    /// Classify a removal noticed at `now`. Without a known expiry there is
+    /// nothing to call it early against, so it counts as expired.
+    #[must_use]
+    pub fn end_reason(&self, now: DateTime<Utc>) -> EndReason {
+        if self.lifecycle_bounds().expires_at.is_some_and(|t| now < t) {
+            EndReason::PulledEarly
+        } else {
+            EndReason::Expired
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        parse_datetime(timestamp).unwrap()
+    }
+
+    fn bounds() -> LifecycleBounds {
+        LifecycleBounds::parse(
+            "2026-01-10T00:00:00Z",
+            "2026-01-20T00:00:00Z",
+            Some("2026-02-01T00:00:00Z"),
+        )
+    }
+
+    #[test]
+    fn state_changes_exactly_at_each_boundary() {
+        let bounds = bounds();
+        let cases = [
+            ("2026-01-09T23:59:59Z", QuestLifecycle::Upcoming),
+            ("2026-01-10T00:00:00Z", QuestLifecycle::Live),
+            ("2026-01-19T23:59:59Z", QuestLifecycle::Live),
+            ("2026-01-20T00:00:00Z", QuestLifecycle::Expired),
+            ("2026-01-31T23:59:59Z", QuestLifecycle::Expired),
+            ("2026-02-01T00:00:00Z", QuestLifecycle::ClaimWindowClosed),
+        ];
+        for (now, expected) in cases {
+            assert_eq!(bounds.state_at(at(now)), expected, "at {now}");
+        }
+    }
+
+    #[test]
+    fn unknown_boundaries_are_never_crossed() {
+        let bounds = LifecycleBounds::parse("not a date", "", None);
+        assert_eq!(
+            bounds.state_at(at("2030-01-01T00:00:00Z")),
+            QuestLifecycle::Live
+        );
+        assert_eq!(bounds.next_transition(at("2030-01-01T00:00:00Z")), None);
+    }
+
+    #[test]
+    fn next_transition_is_the_nearest_future_boundary() {
+        let bounds = bounds();
+        assert_eq!(
+            bounds.next_transition(at("2026-01-05T00:00:00Z")),
+            Some((at("2026-01-10T00:00:00Z"), QuestLifecycle::Live))
+        );
+        assert_eq!(
+            bounds.next_transition(at("2026-01-10T00:00:00Z")),
+            Some((at("2026-01-20T00:00:00Z"), QuestLifecycle::Expired))
+        );
+        assert_eq!(bounds.next_transition(at("2026-02-01T00:00:00Z")), None);
+    }
+}
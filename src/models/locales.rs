@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+
+/// Discord locales known at build time. `extra_locales` in the config adds
+/// new ones without a rebuild.
+pub const LOCALES: &[&str] = &[
+    "en-GB", "en-US", "da-DK", "de-DE", "nl-NL", "no-NO", "fi-FI", "sv-SE", "fr-FR", "it-IT",
+    "es-ES", "es-419", "pt-BR", "hr-HR", "hu-HU", "lt-LT", "pl-PL", "ro-RO", "cs-CZ", "tr-TR",
+    "el-GR", "bg-BG", "ru-RU", "uk-UA", "vi-VN", "hi-IN", "th-TH", "zh-CN", "zh-TW", "ja-JP",
+    "ko-KR",
+];
+
+/// Built-in locale groups. `all` is implicit and always means every known
+/// locale; `[discord.locale_groups]` can add groups or replace these.
+pub const LOCALE_GROUPS: &[(&str, &[&str])] = &[
+    (
+        "eu",
+        &[
+            "en-GB", "da-DK", "de-DE", "nl-NL", "no-NO", "fi-FI", "sv-SE", "fr-FR", "it-IT",
+            "es-ES", "hr-HR", "hu-HU", "lt-LT", "pl-PL", "ro-RO", "cs-CZ", "el-GR", "bg-BG",
+            "uk-UA", "ru-RU", "tr-TR",
+        ],
+    ),
+    ("americas", &["en-US", "es-419", "pt-BR"]),
+    (
+        "asia",
+        &[
+            "vi-VN", "hi-IN", "th-TH", "zh-CN", "zh-TW", "ja-JP", "ko-KR",
+        ],
+    ),
+];
+
+/// Known locale codes and groups, with whatever the config adds.
+pub struct LocaleCatalog {
+    known: Vec<String>,
+    groups: BTreeMap<String, Vec<String>>,
+}
+
+impl LocaleCatalog {
+    /// Build the catalog from the built-in lists plus the config's
+    /// `extra_locales` and `locale_groups`.
+    ///
+    /// # Errors
+    /// Returns a message naming the offending entry if an extra locale is not
+    /// shaped like a locale code or a group lists an unknown locale.
+    pub fn new(
+        extra_locales: &[String],
+        custom_groups: &BTreeMap<String, Vec<String>>,
+    ) -> Result<Self, String> {
+        let mut known: Vec<String> = LOCALES.iter().map(|l| (*l).to_string()).collect();
+        for code in extra_locales {
+            if !looks_like_locale(code) {
+                return Err(format!(
+                    "extra_locales entry \"{code}\" is not a locale code like \"pt-PT\" or \"es-419\""
+                ));
+            }
+            if !known.contains(code) {
+                known.push(code.clone());
+            }
+        }
+
+        let mut catalog = Self {
+            known,
+            groups: LOCALE_GROUPS
+                .iter()
+                .map(|(name, codes)| {
+                    (
+                        (*name).to_string(),
+                        codes.iter().map(|c| (*c).to_string()).collect(),
+                    )
+                })
+                .collect(),
+        };
+        for (name, codes) in custom_groups {
+            for code in codes {
+                catalog
+                    .check_code(code)
+                    .map_err(|e| format!("locale group \"{name}\": {e}"))?;
+            }
+            catalog.groups.insert(name.to_lowercase(), codes.clone());
+        }
+        Ok(catalog)
+    }
+
+    /// Expand locale codes and group names into locale codes, in order and
+    /// without duplicates.
+    ///
+    /// # Errors
+    /// Returns a message naming the first entry that is neither a known
+    /// locale nor a group, with a suggestion where one is close.
+    pub fn resolve(&self, entries: &[String]) -> Result<Vec<String>, String> {
+        let mut locales: Vec<String> = Vec::new();
+        for entry in entries {
+            let expanded = match entry.to_lowercase().as_str() {
+                "all" => self.known.clone(),
+                name => match self.groups.get(name) {
+                    Some(codes) => codes.clone(),
+                    None => vec![self.check_code(entry)?],
+                },
+            };
+            for code in expanded {
+                if !locales.contains(&code) {
+                    locales.push(code);
+                }
+            }
+        }
+        if locales.is_empty() {
+            return Err("locales is empty; list locale codes or groups to check".to_string());
+        }
+        Ok(locales)
+    }
+
+    /// Every known locale.
+    #[must_use]
+    pub fn all(&self) -> Vec<String> {
+        self.known.clone()
+    }
+
+    fn check_code(&self, code: &str) -> Result<String, String> {
+        if self.known.iter().any(|k| k == code) {
+            return Ok(code.to_string());
+        }
+        let normalized = code.replace('_', "-").to_lowercase();
+        let hint = self
+            .known
+            .iter()
+            .find(|k| k.to_lowercase() == normalized)
+            .map(|k| format!(" (did you mean \"{k}\"?)"))
+            .unwrap_or_default();
+        let groups: Vec<&str> = std::iter::once("all")
+            .chain(self.groups.keys().map(String::as_str))
+            .collect();
+        Err(format!(
+            "unknown locale \"{code}\"{hint}; use a Discord locale code, one of the groups {}, \
+             or add new Discord locales under extra_locales",
+            groups.join(", ")
+        ))
+    }
+}
+
+/// `ll`, `lll`, `ll-CC` or `ll-NNN`, the shapes Discord locale codes take.
+fn looks_like_locale(code: &str) -> bool {
+    let mut parts = code.split('-');
+    let language = parts.next().unwrap_or_default();
+    let region = parts.next();
+    if parts.next().is_some()
+        || !(2..=3).contains(&language.len())
+        || !language.chars().all(|c| c.is_ascii_lowercase())
+    {
+        return false;
+    }
+    region.is_none_or(|r| {
+        (r.len() == 2 && r.chars().all(|c| c.is_ascii_uppercase()))
+            || (r.len() == 3 && r.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> LocaleCatalog {
+        LocaleCatalog::new(&[], &BTreeMap::new()).unwrap()
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn resolves_codes_and_groups_in_order_without_duplicates() {
+        let resolved = catalog()
+            .resolve(&strings(&["ko-KR", "AMERICAS", "en-US"]))
+            .unwrap();
+        assert_eq!(resolved, strings(&["ko-KR", "en-US", "es-419", "pt-BR"]));
+        assert_eq!(
+            catalog().resolve(&strings(&["all"])).unwrap().len(),
+            LOCALES.len()
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_empty_lists_with_a_hint() {
+        let err = catalog().resolve(&strings(&["ko_kr"])).unwrap_err();
+        assert!(err.contains("did you mean \"ko-KR\"?"), "{err}");
+        assert!(catalog().resolve(&strings(&["xx-XX"])).is_err());
+        assert!(catalog().resolve(&[]).is_err());
+    }
+
+    #[test]
+    fn config_extends_locales_and_groups() {
+        let groups =
+            BTreeMap::from([("Nordics".to_string(), strings(&["da-DK", "sv-SE", "pt-PT"]))]);
+        let catalog = LocaleCatalog::new(&strings(&["pt-PT"]), &groups).unwrap();
+        assert_eq!(
+            catalog.resolve(&strings(&["nordics"])).unwrap(),
+            strings(&["da-DK", "sv-SE", "pt-PT"])
+        );
+
+        let bad_group = BTreeMap::from([("x".to_string(), strings(&["pt-PT"]))]);
+        assert!(LocaleCatalog::new(&[], &bad_group).is_err());
+        assert!(LocaleCatalog::new(&strings(&["Portuguese"]), &BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn recognises_discord_locale_shapes() {
+        for code in ["en-US", "es-419", "fil", "id"] {
+            assert!(looks_like_locale(code), "{code}");
+        }
+        for code in [
+            "", "e", "EN-us", "en_US", "en-USA", "es-41", "en-US-x", "engl",
+        ] {
+            assert!(!looks_like_locale(code), "{code}");
+        }
+    }
+}
@@ -1,7 +1,25 @@
+pub mod availability;
 pub mod config;
+pub mod cycle;
+pub mod diff;
 pub mod errors;
+pub mod ledger;
+pub mod lifecycle;
+pub mod locales;
 pub mod quest;
+pub mod reward;
+pub mod super_properties;
+pub mod task;
 
+pub use availability::*;
 pub use config::*;
+pub use cycle::*;
+pub use diff::*;
 pub use errors::*;
+pub use ledger::*;
+pub use lifecycle::*;
+pub use locales::*;
 pub use quest::*;
+pub use reward::*;
+pub use super_properties::*;
+pub use task::*;
@@ -1,3 +1,7 @@
+use crate::models::{
+    Delivery, QuestEnd, QuestLifecycle, QuestTaskView, RewardKind, StoredReward, TaskSet,
+};
+use crate::utils::fnv1a_hex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -24,6 +28,9 @@ pub struct QuestConfig {
     pub rewards_config: QuestRewardsConfig,
     pub cta_config: Option<QuestCtaConfig>,
     pub video_metadata: Option<serde_json::Value>,
+    /// Fields Discord sends that we don't model yet, kept so they survive a round trip
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,7 +109,7 @@ pub struct QuestRewardsConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuestReward {
-    pub r#type: u32,
+    pub r#type: RewardKind,
     pub sku_id: String,
     #[serde(default)]
     pub asset: Option<String>,
@@ -134,55 +141,253 @@ pub struct QuestUserStatus {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct QuestsResponse {
-    pub quests: Vec<Quest>,
+    /// Raw quest objects, decoded one by one with `decode` so a single
+    /// unexpected shape doesn't lose every quest in the response
+    pub quests: Vec<serde_json::Value>,
+    /// Raw excluded quest objects, decoded alongside `quests`
     #[serde(default)]
     pub excluded_quests: Vec<serde_json::Value>,
 }
 
+/// A quest the account was excluded from (region, eligibility, ...).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcludedQuest {
+    pub id: String,
+    /// Why the quest is excluded, as Discord reports it
+    #[serde(default, alias = "reason")]
+    pub exclusion_reason: Option<serde_json::Value>,
+    /// Full quest config, when Discord includes it
+    #[serde(default)]
+    pub config: Option<QuestConfig>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Marks fingerprints built by the current `QuestConfig::fingerprint`, so
+/// entries stored with an older scheme aren't reported as changed.
+pub const FINGERPRINT_PREFIX: &str = "v2:";
+
+impl QuestConfig {
+    /// Stable hash of the parts of the config that are the same in every
+    /// locale (ids, dates, rewards, tasks), so one change hashes the same in
+    /// every region. Names and other translated text are left out.
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        let rewards: Vec<serde_json::Value> = self
+            .rewards_config
+            .rewards
+            .iter()
+            .map(|r| serde_json::json!([r.r#type, r.sku_id, r.orb_quantity]))
+            .collect();
+        let tasks: Vec<String> = TaskSet::from_config(self)
+            .map(|set| {
+                set.tasks
+                    .iter()
+                    .map(|t| format!("{:?}:{}:{:?}", t.kind, t.target, t.external_ids))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let canonical = serde_json::json!({
+            "id": self.id,
+            "config_version": self.config_version,
+            "starts_at": self.starts_at,
+            "expires_at": self.expires_at,
+            "application": self.application.id,
+            "rewards": rewards,
+            "rewards_expire_at": self.rewards_config.rewards_expire_at,
+            "platforms": self.rewards_config.platforms,
+            "tasks": tasks,
+        });
+        format!(
+            "{FINGERPRINT_PREFIX}{}",
+            fnv1a_hex(canonical.to_string().as_bytes())
+        )
+    }
+}
+
+impl ExcludedQuest {
+    #[must_use]
+    pub fn reason_text(&self) -> String {
+        match &self.exclusion_reason {
+            None | Some(serde_json::Value::Null) => "unspecified".to_string(),
+            Some(serde_json::Value::String(reason)) => reason.clone(),
+            Some(other) => format!("reason {other}"),
+        }
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.config
+            .as_ref()
+            .map_or("Unknown quest", |c| c.messages.quest_name.as_str())
+    }
+}
+
+/// A quest that could not be decoded into `Quest`, kept as raw JSON.
+#[derive(Debug, Clone)]
+pub struct UndecodedQuest {
+    pub id: Option<String>,
+    pub error: String,
+    pub raw: serde_json::Value,
+}
+
+/// Quests from one response, split by whether they decoded.
+#[derive(Debug, Default)]
+pub struct DecodedQuests {
+    pub quests: Vec<Quest>,
+    pub excluded: Vec<ExcludedQuest>,
+    pub undecoded: Vec<UndecodedQuest>,
+}
+
+impl QuestsResponse {
+    #[must_use]
+    pub fn decode(self) -> DecodedQuests {
+        let mut decoded = DecodedQuests::default();
+        for raw in self.quests {
+            match serde_json::from_value::<Quest>(raw.clone()) {
+                Ok(quest) => decoded.quests.push(quest),
+                Err(e) => decoded.undecoded.push(UndecodedQuest::new(raw, &e)),
+            }
+        }
+        for raw in self.excluded_quests {
+            match serde_json::from_value::<ExcludedQuest>(raw.clone()) {
+                Ok(quest) => decoded.excluded.push(quest),
+                Err(e) => decoded.undecoded.push(UndecodedQuest::new(raw, &e)),
+            }
+        }
+        decoded
+    }
+}
+
+impl UndecodedQuest {
+    fn new(raw: serde_json::Value, error: &serde_json::Error) -> Self {
+        Self {
+            id: raw
+                .get("id")
+                .and_then(serde_json::Value::as_str)
+                .map(ToString::to_string),
+            error: error.to_string(),
+            raw,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredQuest {
     pub id: String,
     pub name: String,
-    pub reward: String,
-    pub reward_type: String,
+    /// Every reward of the quest, in Discord's order
+    #[serde(default)]
+    pub rewards: Vec<StoredReward>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewards_expire_at: Option<String>,
+    #[serde(default)]
+    pub starts_at: String,
     pub expires_at: String,
     pub game_name: String,
+    /// Name of the account (or agent) that first saw this quest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seen_by: Option<String>,
+    /// Seen in `excluded_quests` rather than `quests`; the id is prefixed
+    /// with `EXCLUDED_ID_PREFIX` so it never suppresses a normal sighting
+    #[serde(default)]
+    pub excluded: bool,
+    /// Lifecycle state as of the last check, used to detect transitions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<QuestLifecycle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_version: Option<u32>,
+    /// Task summaries, kept to describe task changes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<String>,
+    /// Hash of the full quest config, used to detect changes to known quests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// Set once the quest is no longer returned for its locale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended: Option<QuestEnd>,
+    /// Announcement status per webhook, for quests announced as new
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deliveries: Vec<Delivery>,
+    /// The quest as fetched, for backends that keep history; not part of the
+    /// stored entry itself
+    #[serde(skip)]
+    pub raw: Option<serde_json::Value>,
+}
+
+pub const EXCLUDED_ID_PREFIX: &str = "excluded-";
+
+impl StoredQuest {
+    /// The quest id without its region prefix.
+    #[must_use]
+    pub fn base_id(&self) -> &str {
+        self.id.split(':').next_back().unwrap_or(self.id.as_str())
+    }
+
+    /// The Discord quest id: `base_id` without the `EXCLUDED_ID_PREFIX`
+    /// that excluded entries carry.
+    #[must_use]
+    pub fn quest_id(&self) -> &str {
+        let base = self.base_id();
+        base.strip_prefix(EXCLUDED_ID_PREFIX).unwrap_or(base)
+    }
+
+    /// The region prefix of the id, if it has one.
+    #[must_use]
+    pub fn region(&self) -> Option<&str> {
+        self.id.split_once(':').map(|(region, _)| region)
+    }
 }
 
 impl From<&Quest> for StoredQuest {
     fn from(quest: &Quest) -> Self {
-        let reward_type = determine_reward_type(&quest.config.rewards_config);
-        let reward = get_reward_name(&quest.config.rewards_config);
-
         Self {
             id: quest.config.id.clone(),
             name: quest.config.messages.quest_name.clone(),
-            reward,
-            reward_type,
+            rewards: StoredReward::all_from(&quest.config.rewards_config),
+            rewards_expire_at: quest.config.rewards_config.rewards_expire_at.clone(),
+            starts_at: quest.config.starts_at.clone(),
             expires_at: quest.config.expires_at.clone(),
             game_name: quest.config.messages.game_title.clone(),
+            seen_by: None,
+            excluded: false,
+            state: Some(quest.lifecycle(chrono::Utc::now())),
+            config_version: Some(quest.config.config_version),
+            tasks: TaskSet::from_config(&quest.config)
+                .map(|set| set.tasks.iter().map(QuestTaskView::summary).collect())
+                .unwrap_or_default(),
+            fingerprint: Some(quest.config.fingerprint()),
+            ended: None,
+            deliveries: Vec::new(),
+            raw: serde_json::to_value(quest).ok(),
         }
     }
 }
 
-fn determine_reward_type(rewards_config: &QuestRewardsConfig) -> String {
-    if rewards_config.rewards.is_empty() {
-        return "other".to_string();
-    }
-
-    let first_reward = &rewards_config.rewards[0];
-    match first_reward.r#type {
-        4 => "orbs".to_string(),
-        3 => "decor".to_string(),
-        1 => "code".to_string(),
-        2 => "ingame".to_string(),
-        _ => "other".to_string(),
+impl From<&ExcludedQuest> for StoredQuest {
+    fn from(quest: &ExcludedQuest) -> Self {
+        let config = quest.config.as_ref();
+        Self {
+            id: format!("{EXCLUDED_ID_PREFIX}{}", quest.id),
+            name: quest.name().to_string(),
+            rewards: config
+                .map(|c| StoredReward::all_from(&c.rewards_config))
+                .unwrap_or_default(),
+            rewards_expire_at: config.and_then(|c| c.rewards_config.rewards_expire_at.clone()),
+            starts_at: config.map(|c| c.starts_at.clone()).unwrap_or_default(),
+            expires_at: config.map(|c| c.expires_at.clone()).unwrap_or_default(),
+            game_name: config
+                .map(|c| c.messages.game_title.clone())
+                .unwrap_or_default(),
+            seen_by: None,
+            excluded: true,
+            state: None,
+            config_version: None,
+            tasks: Vec::new(),
+            fingerprint: None,
+            ended: None,
+            deliveries: Vec::new(),
+            raw: serde_json::to_value(quest).ok(),
+        }
     }
 }
-
-fn get_reward_name(rewards_config: &QuestRewardsConfig) -> String {
-    rewards_config
-        .rewards
-        .first()
-        .map_or_else(|| "Unknown Reward".to_string(), |r| r.messages.name.clone())
-}
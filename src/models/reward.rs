@@ -0,0 +1,101 @@
+use crate::models::QuestRewardsConfig;
+use serde::{Deserialize, Serialize};
+
+/// Quest reward type, as sent in `rewards_config.rewards[].type`.
+///
+/// Serialized as Discord's numeric value so unknown types survive a round trip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "u32", into = "u32")]
+pub enum RewardKind {
+    /// Redeemable code (1)
+    RewardCode,
+    /// Item granted inside the game (2)
+    InGame,
+    /// Discord collectible such as an avatar decoration (3)
+    Collectible,
+    /// Discord Orbs (4)
+    VirtualCurrency,
+    /// Nitro trial (5)
+    FractionalPremium,
+    Unknown(u32),
+}
+
+impl From<u32> for RewardKind {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::RewardCode,
+            2 => Self::InGame,
+            3 => Self::Collectible,
+            4 => Self::VirtualCurrency,
+            5 => Self::FractionalPremium,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl From<RewardKind> for u32 {
+    fn from(kind: RewardKind) -> Self {
+        match kind {
+            RewardKind::RewardCode => 1,
+            RewardKind::InGame => 2,
+            RewardKind::Collectible => 3,
+            RewardKind::VirtualCurrency => 4,
+            RewardKind::FractionalPremium => 5,
+            RewardKind::Unknown(other) => other,
+        }
+    }
+}
+
+impl RewardKind {
+    /// Human-readable name used in notifications.
+    #[must_use]
+    pub fn label(self) -> String {
+        match self {
+            Self::RewardCode => "Reward Code".to_string(),
+            Self::InGame => "In-game Reward".to_string(),
+            Self::Collectible => "Collectible (Decoration)".to_string(),
+            Self::VirtualCurrency => "Orbs".to_string(),
+            Self::FractionalPremium => "Nitro".to_string(),
+            Self::Unknown(value) => format!("Unknown ({value})"),
+        }
+    }
+
+    /// Name used by `reward_filter` ("orbs", "decor", ...).
+    #[must_use]
+    pub fn filter_key(self) -> &'static str {
+        match self {
+            Self::RewardCode => "code",
+            Self::InGame => "ingame",
+            Self::Collectible => "decor",
+            Self::VirtualCurrency => "orbs",
+            Self::FractionalPremium => "nitro",
+            Self::Unknown(_) => "other",
+        }
+    }
+}
+
+/// One reward of a stored quest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredReward {
+    pub kind: RewardKind,
+    pub name: String,
+    pub sku_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orb_quantity: Option<u32>,
+}
+
+impl StoredReward {
+    #[must_use]
+    pub fn all_from(rewards_config: &QuestRewardsConfig) -> Vec<Self> {
+        rewards_config
+            .rewards
+            .iter()
+            .map(|r| Self {
+                kind: r.r#type,
+                name: r.messages.name.clone(),
+                sku_id: r.sku_id.clone(),
+                orb_quantity: r.orb_quantity,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,102 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// Fields of the `X-Super-Properties` header, as configured under
+/// `[discord.client_properties]`. Missing fields take the defaults below.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientProperties {
+    pub os: String,
+    pub browser: String,
+    pub device: String,
+    pub system_locale: String,
+    pub has_client_mods: bool,
+    pub browser_user_agent: String,
+    pub browser_version: String,
+    pub os_version: String,
+    pub referrer: String,
+    pub referring_domain: String,
+    pub referrer_current: String,
+    pub referring_domain_current: String,
+    pub release_channel: String,
+    pub client_build_number: u64,
+    pub client_event_source: Option<String>,
+}
+
+impl Default for ClientProperties {
+    fn default() -> Self {
+        Self {
+            os: "Linux x86_64".to_string(),
+            browser: "Chrome".to_string(),
+            device: String::new(),
+            system_locale: "en-US".to_string(),
+            has_client_mods: false,
+            browser_user_agent: "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/143.0.0.0 Safari/537.36".to_string(),
+            browser_version: "143.0.0.0".to_string(),
+            os_version: String::new(),
+            referrer: String::new(),
+            referring_domain: String::new(),
+            referrer_current: "chrome://newtab/".to_string(),
+            referring_domain_current: "newtab".to_string(),
+            release_channel: "stable".to_string(),
+            client_build_number: 501_820,
+            client_event_source: None,
+        }
+    }
+}
+
+/// Effective `X-Super-Properties` value for an account.
+///
+/// Keeps the decoded JSON alongside the base64 form so it can be shown for
+/// debugging and re-encoded with a per-locale `system_locale`.
+#[derive(Debug, Clone)]
+pub struct SuperProperties {
+    encoded: String,
+    decoded: serde_json::Value,
+}
+
+impl SuperProperties {
+    /// Wrap a base64 blob, decoding it if possible. Returns `None` if the blob
+    /// is not base64-encoded JSON.
+    #[must_use]
+    pub fn from_encoded(blob: &str) -> Option<Self> {
+        let bytes = STANDARD.decode(blob.trim()).ok()?;
+        let decoded = serde_json::from_slice::<serde_json::Value>(&bytes).ok()?;
+        Some(Self {
+            encoded: blob.trim().to_string(),
+            decoded,
+        })
+    }
+
+    #[must_use]
+    pub fn from_properties(props: &ClientProperties) -> Self {
+        Self {
+            encoded: STANDARD.encode(serde_json::to_string(props).unwrap_or_default()),
+            decoded: serde_json::to_value(props).unwrap_or_default(),
+        }
+    }
+
+    #[must_use]
+    pub fn encoded(&self) -> &str {
+        &self.encoded
+    }
+
+    #[must_use]
+    pub fn decoded(&self) -> &serde_json::Value {
+        &self.decoded
+    }
+
+    /// Header value with `system_locale` set to `locale`.
+    #[must_use]
+    pub fn for_locale(&self, locale: &str) -> String {
+        let mut decoded = self.decoded.clone();
+        if let Some(obj) = decoded.as_object_mut() {
+            obj.insert(
+                "system_locale".to_string(),
+                serde_json::Value::String(locale.to_string()),
+            );
+        }
+        STANDARD.encode(decoded.to_string())
+    }
+}
@@ -0,0 +1,212 @@
+use crate::models::QuestConfig;
+
+/// What a quest task asks the user to do, normalised across `task_config`
+/// (v1, keyed by `event_name`) and `task_config_v2` (keyed by `type`).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TaskKind {
+    PlayOnDesktop,
+    PlayOnXbox,
+    PlayOnPlaystation,
+    PlayActivity,
+    StreamOnDesktop,
+    WatchVideo,
+    WatchVideoOnMobile,
+    AchievementInGame,
+    AchievementInActivity,
+    Other(String),
+}
+
+impl TaskKind {
+    #[must_use]
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "PLAY_ON_DESKTOP" | "PLAY_ON_DESKTOP_V2" => Self::PlayOnDesktop,
+            "PLAY_ON_XBOX" => Self::PlayOnXbox,
+            "PLAY_ON_PLAYSTATION" => Self::PlayOnPlaystation,
+            "PLAY_ACTIVITY" => Self::PlayActivity,
+            "STREAM_ON_DESKTOP" => Self::StreamOnDesktop,
+            "WATCH_VIDEO" => Self::WatchVideo,
+            "WATCH_VIDEO_ON_MOBILE" => Self::WatchVideoOnMobile,
+            "ACHIEVEMENT_IN_GAME" => Self::AchievementInGame,
+            "ACHIEVEMENT_IN_ACTIVITY" => Self::AchievementInActivity,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    #[must_use]
+    pub fn label(&self) -> &str {
+        match self {
+            Self::PlayOnDesktop => "Play on desktop",
+            Self::PlayOnXbox => "Play on Xbox",
+            Self::PlayOnPlaystation => "Play on PlayStation",
+            Self::PlayActivity => "Play the Activity",
+            Self::StreamOnDesktop => "Stream on desktop",
+            Self::WatchVideo => "Watch video",
+            Self::WatchVideoOnMobile => "Watch video on mobile",
+            Self::AchievementInGame => "Earn an in-game achievement",
+            Self::AchievementInActivity => "Earn an Activity achievement",
+            Self::Other(name) => name,
+        }
+    }
+
+    /// Platform shown in the quest info, if the task is tied to one.
+    #[must_use]
+    pub fn platform(&self) -> Option<&'static str> {
+        match self {
+            Self::PlayOnDesktop | Self::StreamOnDesktop => Some("🖥️ PC"),
+            Self::PlayOnXbox => Some("🎮 Xbox"),
+            Self::PlayOnPlaystation => Some("🎮 PlayStation"),
+            Self::WatchVideo => Some("📺 Desktop"),
+            Self::WatchVideoOnMobile => Some("📱 Mobile"),
+            _ => None,
+        }
+    }
+
+    /// Whether `target` is a duration in seconds rather than a count.
+    #[must_use]
+    pub fn is_timed(&self) -> bool {
+        !matches!(
+            self,
+            Self::AchievementInGame | Self::AchievementInActivity | Self::Other(_)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinOperator {
+    /// Completing any one task completes the quest
+    Any,
+    /// Every task has to be completed
+    All,
+}
+
+impl JoinOperator {
+    #[must_use]
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "and" | "all" => Self::All,
+            _ => Self::Any,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct QuestTaskView {
+    pub kind: TaskKind,
+    pub target: u32,
+    pub external_ids: Vec<String>,
+}
+
+impl QuestTaskView {
+    /// e.g. "Play on desktop (15 minutes)" or "Earn an in-game achievement (x1)".
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let label = self.kind.label();
+        if self.kind.is_timed() {
+            // target is in seconds; display minutes rounded up (59s -> 1 minute)
+            let minutes = self.target.div_ceil(60);
+            let unit = if minutes == 1 { "minute" } else { "minutes" };
+            format!("{label} ({minutes} {unit})")
+        } else {
+            format!("{label} (x{})", self.target)
+        }
+    }
+}
+
+/// The tasks of a quest, whichever task config version Discord sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskSet {
+    pub join: JoinOperator,
+    pub tasks: Vec<QuestTaskView>,
+}
+
+impl TaskSet {
+    /// Build from `task_config_v2` if present, else from the legacy `task_config`.
+    #[must_use]
+    pub fn from_config(config: &QuestConfig) -> Option<Self> {
+        let mut set = if let Some(v2) = &config.task_config_v2 {
+            Self {
+                join: JoinOperator::from_name(&v2.join_operator),
+                tasks: v2
+                    .tasks
+                    .values()
+                    .map(|t| QuestTaskView {
+                        kind: TaskKind::from_name(&t.r#type),
+                        target: t.target,
+                        external_ids: t.external_ids.clone(),
+                    })
+                    .collect(),
+            }
+        } else {
+            let v1 = config.task_config.as_ref()?;
+            Self {
+                join: JoinOperator::from_name(&v1.join_operator),
+                tasks: v1
+                    .tasks
+                    .values()
+                    .map(|t| QuestTaskView {
+                        kind: TaskKind::from_name(&t.event_name),
+                        target: t.target,
+                        external_ids: t.external_ids.clone(),
+                    })
+                    .collect(),
+            }
+        };
+        // task maps are unordered; sort on every field so rendering and the
+        // config fingerprint don't change between runs
+        set.tasks.sort();
+        Some(set)
+    }
+
+    /// Distinct platforms across all tasks, in task order.
+    #[must_use]
+    pub fn platforms(&self) -> Vec<&'static str> {
+        let mut platforms = Vec::new();
+        for platform in self.tasks.iter().filter_map(|t| t.kind.platform()) {
+            if !platforms.contains(&platform) {
+                platforms.push(platform);
+            }
+        }
+        platforms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Quest;
+
+    /// The first fixture quest with `tasks` as its `task_config_v2` tasks.
+    fn config_with_tasks(tasks: serde_json::Value) -> QuestConfig {
+        let fixture: serde_json::Value =
+            serde_json::from_str(include_str!("../../fixtures/quests/default.json")).unwrap();
+        let mut quest = fixture["quests"][0].clone();
+        quest["config"]["task_config_v2"]["tasks"] = tasks;
+        serde_json::from_value::<Quest>(quest).unwrap().config
+    }
+
+    #[test]
+    fn tasks_of_the_same_kind_sort_on_the_rest() {
+        let config = config_with_tasks(serde_json::json!({
+            "PLAY_ON_DESKTOP_V2": {"type": "PLAY_ON_DESKTOP_V2", "target": 900, "external_ids": []},
+            "PLAY_ON_DESKTOP": {"type": "PLAY_ON_DESKTOP", "target": 600, "external_ids": ["b"]},
+            "PLAY_ON_DESKTOP_ALT": {"type": "PLAY_ON_DESKTOP", "target": 600, "external_ids": ["a"]},
+            "WATCH_VIDEO": {"type": "WATCH_VIDEO", "target": 300, "external_ids": []},
+        }));
+        let set = TaskSet::from_config(&config).unwrap();
+        let order: Vec<(TaskKind, u32, Vec<String>)> = set
+            .tasks
+            .into_iter()
+            .map(|t| (t.kind, t.target, t.external_ids))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (TaskKind::PlayOnDesktop, 600, vec!["a".to_string()]),
+                (TaskKind::PlayOnDesktop, 600, vec!["b".to_string()]),
+                (TaskKind::PlayOnDesktop, 900, vec![]),
+                (TaskKind::WatchVideo, 300, vec![]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use crate::collector;
+use crate::services::{
+    archive, checkpoint::CycleCheckpoint, source::QuestSource, storage::StoreHandle,
+    webhook::WebhookNotifier,
+};
+use log::{error, info};
+
+/// Replay archived responses in the order they were fetched, running each one
+/// through the collector as a single-locale cycle.
+pub async fn replay_archive(
+    dir: &str,
+    store: &StoreHandle,
+    notifiers: &[WebhookNotifier],
+    reward_filter: &str,
+    initial_send_all: bool,
+) -> Result<(), String> {
+    let entries = archive::load_archive(dir);
+    if entries.is_empty() {
+        return Err(format!("no archived responses found in {dir}"));
+    }
+
+    info!("replaying {} archived responses from {dir}", entries.len());
+
+    for entry in &entries {
+        let source = QuestSource::Replay {
+            entries: &entries,
+            until: entry.fetched_at,
+        };
+        match collector::check_quests_all_locales(
+            &source,
+            store,
+            notifiers,
+            reward_filter,
+            &CycleCheckpoint::in_memory(vec![entry.locale.clone()]),
+            initial_send_all,
+            None,
+        )
+        .await
+        {
+            Ok(summary) => summary.log(),
+            Err(e) => error!(
+                "replay of {} response from {} failed: {e}",
+                entry.locale, entry.fetched_at
+            ),
+        }
+    }
+
+    info!("replay finished");
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use crate::models::{QuestLifecycle, StoredQuest};
+use crate::services::{storage::StoreHandle, webhook::WebhookNotifier};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+/// A stored quest that crossed a lifecycle boundary since the last check.
+#[derive(Debug, Clone)]
+pub struct LifecycleEvent {
+    pub quest: StoredQuest,
+    pub from: QuestLifecycle,
+    pub to: QuestLifecycle,
+}
+
+/// Earliest lifecycle boundary after `now` across all stored quests.
+#[must_use]
+pub fn next_boundary(quests: &[StoredQuest], now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    quests
+        .iter()
+        .filter(|q| !q.excluded && q.ended.is_none())
+        .filter_map(|q| q.lifecycle_bounds().next_transition(now))
+        .map(|(at, _)| at)
+        .min()
+}
+
+/// Update each quest's recorded state to its state at `now` and return the
+/// transitions. Quests stored before states were tracked are updated silently.
+pub fn collect_transitions(quests: &mut [StoredQuest], now: DateTime<Utc>) -> Vec<LifecycleEvent> {
+    let mut events = Vec::new();
+    for quest in quests
+        .iter_mut()
+        .filter(|q| !q.excluded && q.ended.is_none())
+    {
+        let current = quest.lifecycle(now);
+        match quest.state.replace(current) {
+            Some(previous) if previous != current => events.push(LifecycleEvent {
+                quest: quest.clone(),
+                from: previous,
+                to: current,
+            }),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Carry recorded states, delivery ledgers and the first `seen_by` over from
+/// `previous`, so a boundary crossed while a cycle was running is still seen
+/// as a transition by the scheduler and failed announcements are still
+/// retried.
+pub fn carry_states(quests: &mut [StoredQuest], previous: &[StoredQuest]) {
+    for quest in quests {
+        if let Some(previous) = previous.iter().find(|p| p.id == quest.id) {
+            if let Some(state) = previous.state {
+                quest.state = Some(state);
+            }
+            if quest.deliveries.is_empty() {
+                quest.deliveries.clone_from(&previous.deliveries);
+            }
+            if previous.seen_by.is_some() {
+                quest.seen_by.clone_from(&previous.seen_by);
+            }
+        }
+    }
+}
+
+/// Apply due lifecycle transitions to storage and announce quests that went
+/// live after being discovered while still upcoming. The states are saved
+/// before anything is sent; a failed send is logged, not retried.
+///
+/// # Errors
+/// Returns error if the store cannot be read or written.
+pub async fn process_transitions(
+    store_handle: &StoreHandle,
+    notifiers: &[WebhookNotifier],
+    announce_live: bool,
+) -> Result<(), String> {
+    let store = store_handle.lock().await;
+    let mut stored = store
+        .load()
+        .map_err(|e| format!("failed to load stored quests: {e}"))?;
+    let events = collect_transitions(&mut stored, Utc::now());
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let mut announced = HashSet::new();
+    let mut now_live = Vec::new();
+    for event in &events {
+        info!(
+            "quest {} ({}) is now {} (was {})",
+            event.quest.id,
+            event.quest.name,
+            event.to.label(),
+            event.from.label()
+        );
+        let base = event.quest.base_id();
+        if event.from == QuestLifecycle::Upcoming
+            && event.to == QuestLifecycle::Live
+            && announced.insert(base.to_string())
+        {
+            now_live.push(event.quest.clone());
+        }
+    }
+
+    store
+        .upsert(&stored)
+        .map_err(|e| format!("failed to save quest states: {e}"))?;
+    drop(store);
+
+    if announce_live && !now_live.is_empty() {
+        for notifier in notifiers {
+            if let Err(e) = notifier.notify_now_live(&now_live).await {
+                warn!(
+                    "failed to send live notifications on notifier {}: {e}",
+                    notifier.display_name()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Wait `interval` for the next fetch cycle, waking up at every lifecycle
+/// boundary in between to process transitions.
+pub async fn wait_for_next_check(
+    store_handle: &StoreHandle,
+    interval: Duration,
+    notifiers: &[WebhookNotifier],
+    announce_live: bool,
+) {
+    let deadline = Instant::now() + interval;
+    loop {
+        let now = Utc::now();
+        let stored = store_handle.lock().await.load().unwrap_or_else(|e| {
+            error!("failed to load stored quests: {e}");
+            Vec::new()
+        });
+        let until_boundary = next_boundary(&stored, now)
+            .and_then(|at| (at - now).to_std().ok())
+            // land just past the boundary so the new state is observed
+            .map(|d| d + Duration::from_secs(1));
+
+        match until_boundary {
+            Some(wait) if Instant::now() + wait < deadline => {
+                debug!("next quest lifecycle boundary in {}s", wait.as_secs());
+                sleep(wait).await;
+                if let Err(e) = process_transitions(store_handle, notifiers, announce_live).await {
+                    error!("error processing quest lifecycle transitions: {e}");
+                }
+            }
+            _ => {
+                sleep(deadline.saturating_duration_since(Instant::now())).await;
+                return;
+            }
+        }
+    }
+}
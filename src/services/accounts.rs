@@ -0,0 +1,126 @@
+use crate::models::{resolve_super_properties, Config, SuperProperties};
+use log::{error, info};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// A Discord account used to fetch quests.
+pub struct Account {
+    pub name: String,
+    pub token: String,
+    pub super_properties: SuperProperties,
+    /// Locales this account is assigned to; empty means "any locale"
+    pub locales: Vec<String>,
+    disabled: AtomicBool,
+}
+
+impl Account {
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        !self.disabled.load(Ordering::Relaxed)
+    }
+}
+
+/// Pool of accounts that locale fetches are spread across.
+///
+/// Accounts assigned to a locale are preferred for it; otherwise unassigned
+/// accounts are handed out round-robin. An account that gets a 401 is
+/// disabled for the rest of the process lifetime.
+pub struct AccountPool {
+    accounts: Vec<Account>,
+    next: AtomicUsize,
+}
+
+impl AccountPool {
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let accounts = config
+            .accounts()
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| Account {
+                name: entry.name.unwrap_or_else(|| format!("account-{}", i + 1)),
+                token: entry.token,
+                super_properties: entry
+                    .super_properties
+                    .as_deref()
+                    .and_then(resolve_super_properties)
+                    .unwrap_or_else(|| config.super_properties()),
+                locales: entry.locales.unwrap_or_default(),
+                disabled: AtomicBool::new(false),
+            })
+            .collect();
+
+        Self {
+            accounts,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Account> {
+        self.accounts.iter()
+    }
+
+    #[must_use]
+    pub fn active_count(&self) -> usize {
+        self.accounts.iter().filter(|a| a.is_active()).count()
+    }
+
+    /// Pick the account that should fetch `locale`, or `None` if every
+    /// eligible account has been disabled.
+    #[must_use]
+    pub fn for_locale(&self, locale: &str) -> Option<&Account> {
+        let assigned: Vec<&Account> = self
+            .accounts
+            .iter()
+            .filter(|a| a.is_active() && a.locales.iter().any(|l| l == locale))
+            .collect();
+
+        let candidates = if assigned.is_empty() {
+            self.accounts
+                .iter()
+                .filter(|a| a.is_active() && a.locales.is_empty())
+                .collect()
+        } else {
+            assigned
+        };
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let idx = self.next.fetch_add(1, Ordering::Relaxed) % candidates.len();
+        Some(candidates[idx])
+    }
+
+    /// Stop using `account`, e.g. after Discord rejected its token.
+    pub fn disable(&self, account: &Account, reason: &str) {
+        if !account.disabled.swap(true, Ordering::Relaxed) {
+            error!(
+                "disabling account {}: {reason} ({} of {} accounts still active)",
+                account.name,
+                self.active_count(),
+                self.len()
+            );
+        }
+    }
+
+    pub fn log_summary(&self) {
+        for account in &self.accounts {
+            let locales = if account.locales.is_empty() {
+                "any".to_string()
+            } else {
+                account.locales.join(", ")
+            };
+            info!("account {} configured for locales: {locales}", account.name);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::utils::{read_json_file, write_json_file};
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One raw `quests/@me` response as Discord returned it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedResponse {
+    pub locale: String,
+    pub fetched_at: DateTime<Utc>,
+    pub status: u16,
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Response body, verbatim
+    pub body: String,
+}
+
+/// Writes every raw quests response to a directory, keeping at most
+/// `max_files` of the newest ones.
+pub struct ResponseArchive {
+    dir: PathBuf,
+    max_files: usize,
+}
+
+impl ResponseArchive {
+    #[must_use]
+    pub fn new(dir: &str, max_files: usize) -> Self {
+        if let Err(e) = fs::create_dir_all(dir) {
+            warn!("failed to create archive directory {dir}: {e}");
+        }
+        info!("archiving raw quest responses to {dir} (keeping {max_files})");
+        Self {
+            dir: PathBuf::from(dir),
+            max_files,
+        }
+    }
+
+    /// Archive a response. Failures are logged, never propagated, so the
+    /// archive can't break fetching.
+    pub fn record(&self, locale: &str, account: &str, status: u16, body: &str) {
+        let entry = ArchivedResponse {
+            locale: locale.to_string(),
+            fetched_at: Utc::now(),
+            status,
+            account: Some(account.to_string()),
+            body: body.to_string(),
+        };
+        let name = format!(
+            "{}_{}.json",
+            entry.fetched_at.format("%Y%m%dT%H%M%S%.3fZ"),
+            locale
+        );
+        let path = self.dir.join(name);
+
+        match write_json_file(&path.to_string_lossy(), &entry) {
+            Ok(()) => debug!("archived response for {locale} to {}", path.display()),
+            Err(e) => warn!("failed to archive response for {locale}: {e}"),
+        }
+
+        self.rotate();
+    }
+
+    fn rotate(&self) {
+        let mut files = archive_files(&self.dir);
+        if files.len() <= self.max_files {
+            return;
+        }
+        let excess = files.len() - self.max_files;
+        for old in files.drain(..excess) {
+            if let Err(e) = fs::remove_file(&old) {
+                warn!("failed to remove old archive {}: {e}", old.display());
+            }
+        }
+    }
+}
+
+/// Load every archived response in `dir`, oldest first.
+#[must_use]
+pub fn load_archive(dir: &str) -> Vec<ArchivedResponse> {
+    let mut entries: Vec<ArchivedResponse> = archive_files(Path::new(dir))
+        .iter()
+        .filter_map(|path| {
+            read_json_file::<ArchivedResponse>(&path.to_string_lossy())
+                .map_err(|e| warn!("skipping unreadable archive {}: {e}", path.display()))
+                .ok()
+        })
+        .collect();
+    entries.sort_by_key(|e| e.fetched_at);
+    entries
+}
+
+/// Archive files in `dir`, sorted by name (and therefore by time).
+fn archive_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = read_dir
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    files
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{sleep_until, Instant};
+
+/// Spaces requests evenly so that no more than `requests_per_minute` start
+/// in any minute, however many callers share it.
+pub struct RequestBudget {
+    requests_per_minute: u32,
+    interval: Duration,
+    /// Earliest moment the next request may start
+    next_slot: Mutex<Instant>,
+}
+
+impl RequestBudget {
+    #[must_use]
+    pub fn per_minute(requests_per_minute: u32) -> Self {
+        let requests_per_minute = requests_per_minute.max(1);
+        Self {
+            requests_per_minute,
+            interval: Duration::from_secs(60) / requests_per_minute,
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    #[must_use]
+    pub fn requests_per_minute(&self) -> u32 {
+        self.requests_per_minute
+    }
+
+    /// Wait for this caller's turn. Slots are handed out in call order.
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next_slot.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        sleep_until(slot).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::future::join_all;
+
+    #[tokio::test]
+    async fn concurrent_callers_are_spaced_one_interval_apart() {
+        // 50ms apart
+        let budget = RequestBudget::per_minute(1200);
+        let start = Instant::now();
+        let mut started: Vec<Duration> = join_all((0..4).map(|_| async {
+            budget.acquire().await;
+            start.elapsed()
+        }))
+        .await;
+        started.sort();
+
+        // the first goes right away, each later one no earlier than its slot
+        assert!(started[0] < Duration::from_millis(40), "{started:?}");
+        for (slot, at) in (0u32..).zip(&started) {
+            assert!(*at >= Duration::from_millis(50) * slot, "{started:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn idle_time_does_not_bank_slots() {
+        let budget = RequestBudget::per_minute(1200);
+        budget.acquire().await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let start = Instant::now();
+        budget.acquire().await;
+        budget.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn a_zero_rate_is_treated_as_one_per_minute() {
+        let budget = RequestBudget::per_minute(0);
+        assert_eq!(budget.requests_per_minute(), 1);
+        assert_eq!(budget.interval, Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::models::LocaleOutcome;
+use crate::utils::{parse_datetime, read_json_file, write_json_file};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// When one locale was last fetched, and how its latest attempt went.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocaleFetch {
+    pub locale: String,
+    /// Last successful fetch
+    pub fetched_at: Option<String>,
+    pub attempted_at: String,
+    /// `LocaleOutcome` label of the latest attempt, e.g. "ok" or "rate limited"
+    pub outcome: String,
+}
+
+/// The locales a collector checks and when each of them was last fetched,
+/// saved after every locale so a restarted collector starts with the ones it
+/// is most behind on instead of the top of the list.
+pub struct CycleCheckpoint {
+    /// `None` keeps the progress in memory only
+    path: Option<String>,
+    locales: Vec<String>,
+    fetches: Mutex<BTreeMap<String, LocaleFetch>>,
+}
+
+impl CycleCheckpoint {
+    /// Load the progress saved at `path`. A missing or unreadable file starts
+    /// with every locale unfetched.
+    #[must_use]
+    pub fn open(path: &str, locales: Vec<String>) -> Self {
+        let fetches: Vec<LocaleFetch> = if Path::new(path).exists() {
+            read_json_file(path)
+                .map_err(|e| warn!("failed to read cycle state file {path}, starting over: {e}"))
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        if !fetches.is_empty() {
+            info!(
+                "loaded last fetch times of {} locales from {path}",
+                fetches.len()
+            );
+        }
+
+        Self {
+            path: Some(path.to_string()),
+            locales,
+            fetches: Mutex::new(fetches.into_iter().map(|f| (f.locale.clone(), f)).collect()),
+        }
+    }
+
+    #[must_use]
+    pub fn in_memory(locales: Vec<String>) -> Self {
+        Self {
+            path: None,
+            locales,
+            fetches: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Every locale to check, in configured order.
+    #[must_use]
+    pub fn locales(&self) -> &[String] {
+        &self.locales
+    }
+
+    /// The locales ordered for the next cycle: never fetched first, then the
+    /// longest since their last successful fetch. Ties keep configured order.
+    #[must_use]
+    pub fn stalest_first(&self) -> Vec<String> {
+        let fetches = self.lock_fetches();
+        let mut locales = self.locales.clone();
+        locales.sort_by_key(|locale| {
+            fetches
+                .get(locale)
+                .and_then(|f| f.fetched_at.as_deref())
+                .and_then(parse_datetime)
+        });
+        locales
+    }
+
+    /// Record how fetching `locale` went and save the progress.
+    pub fn record(&self, locale: &str, outcome: &LocaleOutcome, now: DateTime<Utc>) {
+        let mut fetches = self.lock_fetches();
+        let fetched_at = if outcome.is_success() {
+            Some(now.to_rfc3339())
+        } else {
+            fetches.get(locale).and_then(|f| f.fetched_at.clone())
+        };
+        fetches.insert(
+            locale.to_string(),
+            LocaleFetch {
+                locale: locale.to_string(),
+                fetched_at,
+                attempted_at: now.to_rfc3339(),
+                outcome: outcome.label().to_string(),
+            },
+        );
+
+        if let Some(path) = &self.path {
+            let saved: Vec<&LocaleFetch> = fetches.values().collect();
+            if let Err(e) = write_json_file(path, &saved) {
+                warn!("failed to save cycle state to {path}: {e}");
+            }
+        }
+    }
+
+    /// The last fetch of every configured locale, in configured order;
+    /// `None` for locales never attempted.
+    #[must_use]
+    pub fn fetches(&self) -> Vec<(String, Option<LocaleFetch>)> {
+        let fetches = self.lock_fetches();
+        self.locales
+            .iter()
+            .map(|l| (l.clone(), fetches.get(l).cloned()))
+            .collect()
+    }
+
+    fn lock_fetches(&self) -> MutexGuard<'_, BTreeMap<String, LocaleFetch>> {
+        self.fetches.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
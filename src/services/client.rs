@@ -1,39 +1,245 @@
-use crate::models::{ClientError, Quest, QuestsResponse};
+use crate::models::{ClientError, DecodedQuests, ProxyEntry, QuestsResponse};
+use crate::services::accounts::{Account, AccountPool};
+use crate::services::archive::ResponseArchive;
+use crate::services::budget::RequestBudget;
 use crate::utils::USER_AGENT;
-use log::{debug, info};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// How `QuestClient` retries failed requests.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt for 429, 5xx and connection errors
+    pub max_retries: u32,
+    /// First backoff delay, doubled on every retry
+    pub base_delay: Duration,
+    /// Upper bound for a single backoff delay
+    pub max_delay: Duration,
+    /// Longest `Retry-After` we are willing to sleep through inside one call
+    pub max_rate_limit_wait: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            max_rate_limit_wait: Duration::from_secs(120),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn backoff(&self, retry: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+        let jitter = Duration::from_millis(u64::from(rand::random::<u16>() % 500));
+        exp.min(self.max_delay) + jitter
+    }
+}
 
 pub struct QuestClient {
     client: reqwest::Client,
-    super_properties: String,
+    /// Locale-specific clients routed through a configured proxy
+    locale_clients: HashMap<String, reqwest::Client>,
+    api_url: String,
+    retry: RetryPolicy,
+    localize_super_properties: bool,
+    archive: Option<ResponseArchive>,
+    /// Paces every request this client makes, retries included
+    budget: Option<RequestBudget>,
+    decode_failures: AtomicU64,
 }
 
 impl QuestClient {
     #[must_use]
-    pub fn new(super_properties: String) -> Self {
+    pub fn new(api_url: String) -> Self {
         Self {
-            client: reqwest::Client::new(),
-            super_properties,
+            client: build_http_client(None).unwrap_or_default(),
+            locale_clients: HashMap::new(),
+            api_url,
+            retry: RetryPolicy::default(),
+            localize_super_properties: false,
+            archive: None,
+            budget: None,
+            decode_failures: AtomicU64::new(0),
+        }
+    }
+
+    #[must_use]
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Route requests through the configured proxies. An entry with `locales`
+    /// applies to those locales only; an entry without replaces the default
+    /// client for every other locale.
+    ///
+    /// # Errors
+    /// Returns `ClientError::RequestFailed` if a proxy URL is invalid.
+    pub fn with_proxies(mut self, proxies: &[ProxyEntry]) -> Result<Self, ClientError> {
+        for entry in proxies {
+            let client = build_http_client(Some(&entry.url))?;
+            match &entry.locales {
+                Some(locales) if !locales.is_empty() => {
+                    for locale in locales {
+                        self.locale_clients.insert(locale.clone(), client.clone());
+                    }
+                    info!(
+                        "using proxy {} for {}",
+                        redact_proxy(&entry.url),
+                        locales.join(", ")
+                    );
+                }
+                _ => {
+                    self.client = client;
+                    info!(
+                        "using proxy {} for all other locales",
+                        redact_proxy(&entry.url)
+                    );
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    fn http_for(&self, locale: &str) -> &reqwest::Client {
+        self.locale_clients.get(locale).unwrap_or(&self.client)
+    }
+
+    /// Send each locale's own code as `system_locale` in X-Super-Properties.
+    #[must_use]
+    pub fn with_localized_super_properties(mut self, enabled: bool) -> Self {
+        self.localize_super_properties = enabled;
+        self
+    }
+
+    /// Write every raw quests response to `archive`.
+    #[must_use]
+    pub fn with_archive(mut self, archive: Option<ResponseArchive>) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Share `budget` between all requests to Discord, so concurrent locale
+    /// fetches never exceed it together.
+    #[must_use]
+    pub fn with_request_budget(mut self, budget: RequestBudget) -> Self {
+        info!(
+            "limiting Discord API requests to {} per minute",
+            budget.requests_per_minute()
+        );
+        self.budget = Some(budget);
+        self
+    }
+
+    async fn wait_for_budget(&self) {
+        if let Some(budget) = &self.budget {
+            budget.acquire().await;
         }
     }
 
-    /// Fetch quests from direct API given locale code
+    /// Number of quests that failed to decode since startup. A non-zero value
+    /// usually means Discord changed the quest schema.
+    #[must_use]
+    pub fn decode_failures(&self) -> u64 {
+        self.decode_failures.load(Ordering::Relaxed)
+    }
+
+    /// Fetch quests for `locale` with an account from `pool`, moving on to the
+    /// next eligible account whenever one is rejected with 401.
     ///
     /// # Errors
-    /// Returns `ClientError` if request fails or response parsing fails.
+    /// Returns `ClientError::NoActiveAccount` if no usable account is left,
+    /// otherwise the error from `fetch_quests_with_locale`.
+    pub async fn fetch_quests_from_pool<'a>(
+        &self,
+        pool: &'a AccountPool,
+        locale: &str,
+    ) -> Result<(DecodedQuests, &'a Account), ClientError> {
+        loop {
+            let Some(account) = pool.for_locale(locale) else {
+                return Err(ClientError::NoActiveAccount(locale.to_string()));
+            };
+            match self.fetch_quests_with_locale(account, locale).await {
+                Err(ClientError::HttpError(401)) => {
+                    pool.disable(account, "token rejected with 401");
+                }
+                other => return other.map(|quests| (quests, account)),
+            }
+        }
+    }
+
+    /// Fetch quests from direct API given locale code, retrying rate limits,
+    /// server errors and connection failures according to the retry policy.
+    ///
+    /// # Errors
+    /// Returns `ClientError::RateLimited` if Discord asks us to wait longer than
+    /// the policy allows, `ClientError::RetriesExhausted` once retries run out,
+    /// and other variants for failures that are not worth retrying.
     pub async fn fetch_quests_with_locale(
         &self,
-        token: &str,
+        account: &Account,
         locale: &str,
-    ) -> Result<Vec<Quest>, ClientError> {
-        debug!("fetching quests via direct API for locale: {locale}");
+    ) -> Result<DecodedQuests, ClientError> {
+        let mut retries = 0;
+
+        loop {
+            let err = match self.fetch_once(account, locale).await {
+                Ok(quests) => return Ok(quests),
+                Err(e) => e,
+            };
 
+            let wait = match &err {
+                ClientError::RateLimited { retry_after } => {
+                    if *retry_after > self.retry.max_rate_limit_wait {
+                        return Err(err);
+                    }
+                    *retry_after
+                }
+                ClientError::ServerError(_) => self.retry.backoff(retries + 1),
+                ClientError::RequestFailed(e) if e.is_connect() || e.is_timeout() => {
+                    self.retry.backoff(retries + 1)
+                }
+                _ => return Err(err),
+            };
+
+            if retries >= self.retry.max_retries {
+                return Err(ClientError::RetriesExhausted {
+                    attempts: retries + 1,
+                    last: Box::new(err),
+                });
+            }
+
+            retries += 1;
+            warn!(
+                "quests request for {locale} failed ({err}); retry {retries}/{} in {:.1}s",
+                self.retry.max_retries,
+                wait.as_secs_f64()
+            );
+            sleep(wait).await;
+        }
+    }
+
+    /// Confirm that Discord accepts the account's token by calling `users/@me`.
+    ///
+    /// # Errors
+    /// Returns `ClientError::HttpError` with the status code if the token is
+    /// rejected, or `ClientError::RequestFailed` if the request itself fails.
+    pub async fn validate_account(&self, account: &Account) -> Result<String, ClientError> {
+        self.wait_for_budget().await;
         let response = self
             .client
-            .get("https://discord.com/api/v10/quests/@me")
-            .header("Authorization", token)
+            .get(format!("{}/users/@me", self.api_url))
+            .header("Authorization", &account.token)
             .header("User-Agent", USER_AGENT)
-            .header("X-Discord-Locale", locale)
-            .header("X-Super-Properties", &self.super_properties)
+            .header("X-Super-Properties", account.super_properties.encoded())
             .send()
             .await
             .map_err(ClientError::RequestFailed)?;
@@ -42,13 +248,229 @@ impl QuestClient {
             return Err(ClientError::HttpError(response.status().as_u16()));
         }
 
-        let data: QuestsResponse = response.json().await.map_err(ClientError::RequestFailed)?;
+        let user: serde_json::Value = response.json().await.map_err(ClientError::RequestFailed)?;
+        Ok(user
+            .get("username")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("unknown")
+            .to_string())
+    }
+
+    async fn fetch_once(
+        &self,
+        account: &Account,
+        locale: &str,
+    ) -> Result<DecodedQuests, ClientError> {
+        debug!(
+            "fetching quests via direct API for locale: {locale} (account: {})",
+            account.name
+        );
+
+        let super_properties = if self.localize_super_properties {
+            account.super_properties.for_locale(locale)
+        } else {
+            account.super_properties.encoded().to_string()
+        };
+
+        self.wait_for_budget().await;
+        let response = self
+            .http_for(locale)
+            .get(format!("{}/quests/@me", self.api_url))
+            .header("Authorization", &account.token)
+            .header("User-Agent", USER_AGENT)
+            .header("X-Discord-Locale", locale)
+            .header("X-Super-Properties", super_properties)
+            .send()
+            .await
+            .map_err(ClientError::RequestFailed)?;
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().await.map_err(ClientError::RequestFailed)?;
+
+        if let Some(archive) = &self.archive {
+            archive.record(locale, &account.name, status.as_u16(), &body);
+        }
+
+        let decoded = decode_quests_response(status.as_u16(), &headers, &body, locale)?;
+
+        if !decoded.undecoded.is_empty() {
+            let total = self
+                .decode_failures
+                .fetch_add(decoded.undecoded.len() as u64, Ordering::Relaxed)
+                + decoded.undecoded.len() as u64;
+            warn!("{total} quest decode failures since startup");
+        }
 
         info!(
-            "fetched {} quests from API (locale: {})",
-            data.quests.len(),
-            locale
+            "fetched {} quests from API (locale: {}, account: {})",
+            decoded.quests.len(),
+            locale,
+            account.name
+        );
+        Ok(decoded)
+    }
+}
+
+/// Turn a raw quests response into quests, or the matching `ClientError`.
+/// Quests that fail to decode are logged and returned in `undecoded`.
+///
+/// # Errors
+/// Returns `RateLimited`, `ServerError` or `HttpError` for non-2xx statuses and
+/// `Decode` if the body is not a quests response at all.
+pub fn decode_quests_response(
+    status: u16,
+    headers: &reqwest::header::HeaderMap,
+    body: &str,
+    locale: &str,
+) -> Result<DecodedQuests, ClientError> {
+    if status == 429 {
+        let header_wait = rate_limit_wait(headers);
+        let body_wait = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|body| body.get("retry_after").and_then(serde_json::Value::as_f64));
+        let secs = header_wait.or(body_wait).unwrap_or(1.0);
+        // the value is the server's; one too large (or infinite) to be a
+        // Duration means "longer than any retry policy waits"
+        return Err(ClientError::RateLimited {
+            retry_after: Duration::try_from_secs_f64(secs.max(0.0)).unwrap_or(Duration::MAX),
+        });
+    }
+    if (500..600).contains(&status) {
+        return Err(ClientError::ServerError(status));
+    }
+    if !(200..300).contains(&status) {
+        return Err(ClientError::HttpError(status));
+    }
+
+    let decoded = serde_json::from_str::<QuestsResponse>(body)?.decode();
+    for bad in &decoded.undecoded {
+        warn!(
+            "failed to decode quest {} (locale: {locale}): {}",
+            bad.id.as_deref().unwrap_or("(no id)"),
+            bad.error
+        );
+    }
+    Ok(decoded)
+}
+
+/// Seconds to wait from `Retry-After` or `X-RateLimit-Reset-After`, whichever is larger.
+fn rate_limit_wait(headers: &reqwest::header::HeaderMap) -> Option<f64> {
+    ["retry-after", "x-ratelimit-reset-after"]
+        .iter()
+        .filter_map(|name| {
+            headers
+                .get(*name)?
+                .to_str()
+                .ok()?
+                .trim()
+                .parse::<f64>()
+                .ok()
+        })
+        .reduce(f64::max)
+}
+
+fn build_http_client(proxy: Option<&str>) -> Result<reqwest::Client, ClientError> {
+    let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(30));
+    if let Some(url) = proxy {
+        builder = builder.proxy(reqwest::Proxy::all(url).map_err(ClientError::RequestFailed)?);
+    }
+    builder.build().map_err(ClientError::RequestFailed)
+}
+
+/// Proxy URL with any credentials stripped, for logging.
+fn redact_proxy(url: &str) -> String {
+    reqwest::Url::parse(url).map_or_else(
+        |_| "(invalid proxy url)".to_string(),
+        |mut parsed| {
+            let _ = parsed.set_username("");
+            let _ = parsed.set_password(None);
+            parsed.to_string()
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| {
+                (
+                    reqwest::header::HeaderName::from_static(name),
+                    HeaderValue::from_static(value),
+                )
+            })
+            .collect()
+    }
+
+    fn retry_after(headers: &HeaderMap, body: &str) -> Duration {
+        match decode_quests_response(429, headers, body, "en-US") {
+            Err(ClientError::RateLimited { retry_after }) => retry_after,
+            other => panic!(
+                "expected RateLimited, got {:?}",
+                other.map(|d| d.quests.len())
+            ),
+        }
+    }
+
+    #[test]
+    fn rate_limit_wait_prefers_the_larger_header() {
+        let headers = headers(&[("retry-after", "2"), ("x-ratelimit-reset-after", "3.5")]);
+        assert_eq!(
+            retry_after(&headers, r#"{"retry_after": 10}"#),
+            Duration::from_secs_f64(3.5)
         );
-        Ok(data.quests)
+    }
+
+    #[test]
+    fn rate_limit_wait_falls_back_to_the_body_then_one_second() {
+        let empty = HeaderMap::new();
+        assert_eq!(
+            retry_after(&empty, r#"{"retry_after": 0.25}"#),
+            Duration::from_millis(250)
+        );
+        assert_eq!(retry_after(&empty, "not json"), Duration::from_secs(1));
+        assert_eq!(
+            retry_after(&headers(&[("retry-after", "soon")]), "{}"),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn rate_limit_wait_out_of_range_is_clamped() {
+        let empty = HeaderMap::new();
+        assert_eq!(
+            retry_after(&empty, r#"{"retry_after": -5}"#),
+            Duration::ZERO
+        );
+        assert_eq!(
+            retry_after(&empty, r#"{"retry_after": 1e300}"#),
+            Duration::MAX
+        );
+        assert_eq!(
+            retry_after(&headers(&[("retry-after", "inf")]), "{}"),
+            Duration::MAX
+        );
+    }
+
+    #[test]
+    fn other_statuses_map_to_their_errors() {
+        let empty = HeaderMap::new();
+        assert!(matches!(
+            decode_quests_response(503, &empty, "", "en-US"),
+            Err(ClientError::ServerError(503))
+        ));
+        assert!(matches!(
+            decode_quests_response(403, &empty, "", "en-US"),
+            Err(ClientError::HttpError(403))
+        ));
+        assert!(matches!(
+            decode_quests_response(200, &empty, "[]", "en-US"),
+            Err(ClientError::Decode(_))
+        ));
     }
 }
@@ -0,0 +1,314 @@
+use crate::models::{Availability, Delivery, DeliveryStatus, NotifyError, Quest, StoredQuest};
+use crate::services::webhook::WebhookNotifier;
+use crate::utils::parse_datetime;
+use chrono::{DateTime, Duration, Utc};
+use log::{error, info, warn};
+use std::collections::BTreeMap;
+
+/// How a webhook's failed quest announcements are retried on later cycles.
+#[derive(Debug, Clone)]
+pub struct DeliveryPolicy {
+    /// Attempts before a delivery is marked dead, the first one included
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled on every retry
+    pub base_delay: Duration,
+    /// Upper bound for a single wait
+    pub max_delay: Duration,
+}
+
+impl Default for DeliveryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::minutes(5),
+            max_delay: Duration::hours(6),
+        }
+    }
+}
+
+impl DeliveryPolicy {
+    fn retry_delay(&self, attempts: u32) -> Duration {
+        let factor = 2i32.saturating_pow(attempts.saturating_sub(1));
+        self.base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// The ledger entry for attempt number `attempts` on `webhook`. Webhooks
+    /// that are gone for good (401/403/404) are not retried.
+    fn record(
+        &self,
+        webhook: &str,
+        attempts: u32,
+        result: Result<(), NotifyError>,
+        now: DateTime<Utc>,
+    ) -> Delivery {
+        let (status, retry_at, last_error) = match result {
+            Ok(()) => (DeliveryStatus::Delivered, None, None),
+            Err(e) if attempts >= self.max_attempts || e.is_definitive() => {
+                (DeliveryStatus::Dead, None, Some(e.to_string()))
+            }
+            Err(e) => (
+                DeliveryStatus::Failed,
+                Some((now + self.retry_delay(attempts)).to_rfc3339()),
+                Some(e.to_string()),
+            ),
+        };
+        Delivery {
+            webhook: webhook.to_string(),
+            status,
+            attempts,
+            last_attempt_at: now.to_rfc3339(),
+            retry_at,
+            last_error,
+        }
+    }
+}
+
+impl StoredQuest {
+    /// Replace the ledger entry for the delivery's webhook, or add it.
+    pub fn record_delivery(&mut self, delivery: Delivery) {
+        match self
+            .deliveries
+            .iter_mut()
+            .find(|d| d.webhook == delivery.webhook)
+        {
+            Some(slot) => *slot = delivery,
+            None => self.deliveries.push(delivery),
+        }
+    }
+
+    /// Failed deliveries whose next attempt is due at `now`.
+    #[must_use]
+    pub fn due_deliveries(&self, now: DateTime<Utc>) -> Vec<Delivery> {
+        self.deliveries
+            .iter()
+            .filter(|d| d.status == DeliveryStatus::Failed)
+            .filter(|d| {
+                d.retry_at
+                    .as_deref()
+                    .and_then(parse_datetime)
+                    .is_none_or(|at| at <= now)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Announce `quests` on every notifier, recording each outcome on the
+/// entries of `entries` with the same quest id. `availability` is keyed by
+/// quest id. A failed send never stops the others; it is left in the ledger
+/// for `retry_due`.
+pub async fn announce_new(
+    notifiers: &[WebhookNotifier],
+    quests: &[Quest],
+    entries: &mut [StoredQuest],
+    availability: &BTreeMap<String, Availability>,
+    now: DateTime<Utc>,
+) {
+    for quest in quests {
+        let available = availability.get(&quest.config.id);
+        for notifier in notifiers {
+            let result = notifier
+                .send_full_quest_notification(quest, available)
+                .await;
+            if let Err(e) = &result {
+                warn!(
+                    "failed to announce quest {} on notifier {}: {e}",
+                    quest.config.id,
+                    notifier.display_name()
+                );
+            }
+            let delivery =
+                notifier
+                    .delivery_policy()
+                    .record(&notifier.ledger_key(), 1, result, now);
+            report_dead(&delivery, &quest.config.messages.quest_name, notifier);
+            for entry in entries
+                .iter_mut()
+                .filter(|e| e.base_id() == quest.config.id)
+            {
+                entry.record_delivery(delivery.clone());
+            }
+        }
+    }
+    if !quests.is_empty() {
+        info!(
+            "announced {} new quests on {} notifiers",
+            quests.len(),
+            notifiers.len()
+        );
+    }
+}
+
+/// Retry the failed deliveries of the `region` entries of `stored` that are
+/// due at `now`. `quests` is the latest response for `region`; a quest that is
+/// no longer in it can't be announced from there anymore, so that entry's
+/// deliveries are marked dead. One send covers the quest in every region, so
+/// its outcome is recorded on all entries of `stored` with the same quest id
+/// that were waiting on the same webhook. Returns the entries whose ledger
+/// changed.
+pub async fn retry_due(
+    notifiers: &[WebhookNotifier],
+    quests: &[Quest],
+    region: &str,
+    stored: &[StoredQuest],
+    availability: &BTreeMap<String, Availability>,
+    now: DateTime<Utc>,
+) -> Vec<StoredQuest> {
+    let mut changed: Vec<StoredQuest> = Vec::new();
+    for entry in stored.iter().filter(|e| e.region() == Some(region)) {
+        let quest = quests.iter().find(|q| q.config.id == entry.base_id());
+        for previous in entry.due_deliveries(now) {
+            let notifier = notifiers
+                .iter()
+                .find(|n| n.ledger_key() == previous.webhook);
+            let (delivery, everywhere) = match (quest, notifier) {
+                (Some(quest), Some(notifier)) => {
+                    let policy = notifier.delivery_policy();
+                    let attempts = previous.attempts + 1;
+                    info!(
+                        "retrying announcement of quest {} on notifier {} (attempt {attempts}/{})",
+                        entry.id,
+                        notifier.display_name(),
+                        policy.max_attempts
+                    );
+                    let result = notifier
+                        .send_full_quest_notification(quest, availability.get(entry.base_id()))
+                        .await;
+                    let delivery = policy.record(&previous.webhook, attempts, result, now);
+                    report_dead(&delivery, &entry.name, notifier);
+                    (delivery, true)
+                }
+                (None, _) => (give_up(previous, "quest is no longer listed", entry), false),
+                (_, None) => (
+                    give_up(previous, "webhook is no longer configured", entry),
+                    true,
+                ),
+            };
+            let targets = stored.iter().filter(|s| {
+                s.id == entry.id
+                    || (everywhere
+                        && s.base_id() == entry.base_id()
+                        && s.deliveries.iter().any(|d| {
+                            d.webhook == delivery.webhook && d.status == DeliveryStatus::Failed
+                        }))
+            });
+            for target in targets {
+                match changed.iter_mut().find(|c| c.id == target.id) {
+                    Some(updated) => updated.record_delivery(delivery.clone()),
+                    None => {
+                        let mut updated = target.clone();
+                        updated.record_delivery(delivery.clone());
+                        changed.push(updated);
+                    }
+                }
+            }
+        }
+    }
+    changed
+}
+
+fn report_dead(delivery: &Delivery, quest_name: &str, notifier: &WebhookNotifier) {
+    if delivery.status == DeliveryStatus::Dead {
+        error!(
+            "giving up announcing quest {quest_name} on notifier {} after {} attempts: {}",
+            notifier.display_name(),
+            delivery.attempts,
+            delivery.last_error.as_deref().unwrap_or("unknown error")
+        );
+    }
+}
+
+fn give_up(mut delivery: Delivery, reason: &str, entry: &StoredQuest) -> Delivery {
+    error!(
+        "giving up announcing quest {} ({}) on webhook {}: {reason}",
+        entry.id, entry.name, delivery.webhook
+    );
+    delivery.status = DeliveryStatus::Dead;
+    delivery.retry_at = None;
+    delivery.last_error = Some(reason.to_string());
+    delivery
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        parse_datetime("2026-01-15T00:00:00Z").unwrap()
+    }
+
+    fn policy() -> DeliveryPolicy {
+        DeliveryPolicy {
+            max_attempts: 3,
+            ..DeliveryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_cap() {
+        let policy = policy();
+        let minutes = |attempts| policy.retry_delay(attempts).num_minutes();
+        assert_eq!(minutes(1), 5);
+        assert_eq!(minutes(2), 10);
+        assert_eq!(minutes(3), 20);
+        assert_eq!(minutes(7), 320);
+        assert_eq!(minutes(8), 360);
+        assert_eq!(minutes(u32::MAX), 360);
+    }
+
+    #[test]
+    fn a_failure_is_retried_until_max_attempts() {
+        let policy = policy();
+        let failed = policy.record("hook", 1, Err(NotifyError::HttpStatus(500)), now());
+        assert_eq!(failed.status, DeliveryStatus::Failed);
+        assert_eq!(
+            failed.retry_at.as_deref().and_then(parse_datetime),
+            Some(now() + Duration::minutes(5))
+        );
+        assert_eq!(
+            failed.last_error.as_deref(),
+            Some("Webhook responded with status 500")
+        );
+
+        let dead = policy.record("hook", 3, Err(NotifyError::HttpStatus(500)), now());
+        assert_eq!(dead.status, DeliveryStatus::Dead);
+        assert_eq!(dead.retry_at, None);
+
+        let delivered = policy.record("hook", 3, Ok(()), now());
+        assert_eq!(delivered.status, DeliveryStatus::Delivered);
+        assert_eq!(delivered.last_error, None);
+    }
+
+    #[test]
+    fn a_webhook_that_is_gone_is_not_retried() {
+        let delivery = policy().record("hook", 1, Err(NotifyError::HttpStatus(404)), now());
+        assert_eq!(delivery.status, DeliveryStatus::Dead);
+    }
+
+    #[test]
+    fn only_failed_deliveries_past_their_retry_time_are_due() {
+        let policy = policy();
+        let mut quest: StoredQuest = serde_json::from_value(serde_json::json!({
+            "id": "en-US:1",
+            "name": "Quest",
+            "expires_at": "2026-02-01T00:00:00Z",
+            "game_name": "Game",
+        }))
+        .unwrap();
+        let earlier = now() - Duration::minutes(10);
+        quest.record_delivery(policy.record("due", 1, Err(NotifyError::HttpStatus(500)), earlier));
+        quest.record_delivery(policy.record("later", 1, Err(NotifyError::HttpStatus(500)), now()));
+        quest.record_delivery(policy.record("dead", 3, Err(NotifyError::HttpStatus(500)), earlier));
+        quest.record_delivery(policy.record("sent", 1, Ok(()), earlier));
+
+        let due: Vec<String> = quest
+            .due_deliveries(now())
+            .into_iter()
+            .map(|d| d.webhook)
+            .collect();
+        assert_eq!(due, ["due"]);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::models::ClientError;
+use crate::services::accounts::AccountPool;
+use crate::services::webhook::WebhookNotifier;
+use crate::services::QuestClient;
+use log::{error, info, warn};
+use std::fmt;
+
+/// Outcome of a single startup check.
+pub enum CheckStatus {
+    Ok(String),
+    Failed(String),
+    /// The check could not be completed (network error, 5xx, rate limit)
+    Inconclusive(String),
+}
+
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.status {
+            CheckStatus::Ok(detail) => write!(f, "[ OK ] {} - {detail}", self.name),
+            CheckStatus::Failed(detail) => write!(f, "[FAIL] {} - {detail}", self.name),
+            CheckStatus::Inconclusive(detail) => write!(f, "[ ?? ] {} - {detail}", self.name),
+        }
+    }
+}
+
+/// Consolidated result of validating accounts and webhooks before the main loop.
+pub struct StartupReport {
+    pub accounts: Vec<CheckResult>,
+    pub webhooks: Vec<CheckResult>,
+}
+
+impl StartupReport {
+    /// `false` if any token or webhook was positively identified as broken.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.accounts
+            .iter()
+            .chain(&self.webhooks)
+            .all(|c| !matches!(c.status, CheckStatus::Failed(_)))
+    }
+
+    pub fn log(&self) {
+        info!("startup validation report:");
+        for (section, checks) in [("accounts", &self.accounts), ("webhooks", &self.webhooks)] {
+            if checks.is_empty() {
+                continue;
+            }
+            info!("  {section}:");
+            for check in checks {
+                match check.status {
+                    CheckStatus::Ok(_) => info!("    {check}"),
+                    CheckStatus::Failed(_) => error!("    {check}"),
+                    CheckStatus::Inconclusive(_) => warn!("    {check}"),
+                }
+            }
+        }
+    }
+}
+
+/// Confirm every account token is accepted by Discord and every webhook still
+/// exists. Accounts whose token is rejected are disabled in the pool.
+pub async fn run_startup_checks(
+    client: &QuestClient,
+    accounts: &AccountPool,
+    notifiers: &[WebhookNotifier],
+) -> StartupReport {
+    let mut account_checks = Vec::new();
+    for account in accounts.iter() {
+        let status = match client.validate_account(account).await {
+            Ok(username) => CheckStatus::Ok(format!("token valid (user: {username})")),
+            Err(ClientError::HttpError(code @ (401 | 403))) => {
+                accounts.disable(account, "token rejected during startup validation");
+                CheckStatus::Failed(format!("token rejected with {code}"))
+            }
+            Err(e) => CheckStatus::Inconclusive(e.to_string()),
+        };
+        account_checks.push(CheckResult {
+            name: account.name.clone(),
+            status,
+        });
+    }
+
+    let mut webhook_checks = Vec::new();
+    for notifier in notifiers {
+        let status = match notifier.check().await {
+            Ok(name) => CheckStatus::Ok(format!("webhook exists (name: {name})")),
+            Err(e) if e.is_definitive() => CheckStatus::Failed(e.to_string()),
+            Err(e) => CheckStatus::Inconclusive(e.to_string()),
+        };
+        webhook_checks.push(CheckResult {
+            name: notifier.display_name().to_string(),
+            status,
+        });
+    }
+
+    StartupReport {
+        accounts: account_checks,
+        webhooks: webhook_checks,
+    }
+}
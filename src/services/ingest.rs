@@ -1,7 +1,12 @@
-use crate::models::{Quest, StoredQuest};
-use crate::services::{storage, webhook::WebhookNotifier};
+use crate::models::{ExcludedQuest, Quest, StoredQuest, EXCLUDED_ID_PREFIX};
+use crate::services::{
+    delivery,
+    storage::{self, StoreHandle},
+    webhook::WebhookNotifier,
+};
 use axum::http::StatusCode;
 use axum::{extract::State, routing::post, Json, Router};
+use chrono::Utc;
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
@@ -12,6 +17,9 @@ use std::sync::Arc;
 pub struct IngestState {
     pub accept_token: Option<String>,
     pub notifiers: Arc<Vec<WebhookNotifier>>,
+    pub store: StoreHandle,
+    /// The collector's own locales, against which a quest counts as global
+    pub locales: Arc<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -19,7 +27,12 @@ pub struct IngestPayload {
     pub region: String,
     pub quests: Vec<Quest>,
     #[serde(default)]
+    pub excluded_quests: Vec<ExcludedQuest>,
+    #[serde(default)]
     pub source: Option<String>,
+    /// Name of the agent account that fetched these quests
+    #[serde(default)]
+    pub account: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -36,10 +49,14 @@ pub async fn start_server(
     accept_token: Option<String>,
     port: u16,
     notifiers: Vec<WebhookNotifier>,
+    store: StoreHandle,
+    locales: Vec<String>,
 ) {
     let state = IngestState {
         accept_token,
         notifiers: Arc::new(notifiers),
+        store,
+        locales: Arc::new(locales),
     };
     let app = Router::new()
         .route("/ingest", post(ingest_handler))
@@ -85,22 +102,118 @@ async fn ingest_handler(
     }
 
     info!(
-        "received ingest from source: {:?} for region: {}",
-        payload.source, payload.region
+        "received ingest from source: {:?} (account: {:?}) for region: {}",
+        payload.source, payload.account, payload.region
     );
 
     // convert quests to StoredQuest and prefix id with region for regional dedupe
-    let mut stored = storage::load_stored_quests();
-    let before = stored.len();
-
+    let storage_err =
+        |e: crate::models::StorageError| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
     let mut new_entries: Vec<StoredQuest> = Vec::new();
-    for q in &payload.quests {
-        let mut sq = StoredQuest::from(q);
+    let entries = payload
+        .quests
+        .iter()
+        .map(StoredQuest::from)
+        .chain(payload.excluded_quests.iter().map(StoredQuest::from));
+    for mut sq in entries {
         sq.id = format!("{}:{}", payload.region, sq.id);
+        sq.seen_by.clone_from(&payload.account);
         new_entries.push(sq);
     }
 
-    let new_only = storage::find_new_quests(&new_entries, &stored);
+    // quests the region no longer lists end here too, as they do for the
+    // collector's own locales
+    let present_ids: HashSet<String> = payload
+        .quests
+        .iter()
+        .map(|q| q.config.id.clone())
+        .chain(
+            payload
+                .excluded_quests
+                .iter()
+                .map(|eq| format!("{EXCLUDED_ID_PREFIX}{}", eq.id)),
+        )
+        .collect();
+
+    // work out what changed and save it under the store lock; webhooks are
+    // only called once it is released, as in the collector, so a slow agent
+    // report doesn't hold up the collector cycle or the lifecycle scheduler
+    let (before, stored, ended, updates, mut new_only) = {
+        let store = state.store.lock().await;
+        let mut stored = store.load().map_err(storage_err)?;
+        let before = stored.len();
+
+        let (removed_excluded, removed): (Vec<_>, Vec<_>) = storage::find_removed_quests(
+            &store.by_region(&payload.region).map_err(storage_err)?,
+            &payload.region,
+            &present_ids,
+            Utc::now(),
+        )
+        .into_iter()
+        .partition(|q| q.excluded);
+        if !removed_excluded.is_empty() {
+            let gone: HashSet<&str> = removed_excluded.iter().map(|q| q.id.as_str()).collect();
+            store
+                .prune(&|q| !gone.contains(q.id.as_str()))
+                .map_err(storage_err)?;
+        }
+        let ended = storage::ended_to_announce(&*store, &removed).map_err(storage_err)?;
+        if !removed.is_empty() {
+            for quest in &removed {
+                info!(
+                    "quest {} ({}) is gone from region {}",
+                    quest.id, quest.name, payload.region
+                );
+            }
+            store.mark_ended(&removed).map_err(storage_err)?;
+        }
+        if !removed_excluded.is_empty() || !removed.is_empty() {
+            stored = store.load().map_err(storage_err)?;
+        }
+
+        let updates = storage::find_updated_quests(&new_entries, &stored);
+        let refreshed = storage::refresh_known_quests(&mut stored, &new_entries);
+        store.upsert(&refreshed).map_err(storage_err)?;
+
+        let new_only = store.find_new(&new_entries).map_err(storage_err)?;
+        (before, stored, ended, updates, new_only)
+    };
+
+    for notifier in state.notifiers.iter() {
+        if let Err(e) = notifier.notify_ended(&ended, &payload.region).await {
+            warn!("failed to send ended notification for ingested quests: {e}");
+        }
+    }
+
+    if !updates.is_empty() {
+        for notifier in state.notifiers.iter() {
+            if let Err(e) = notifier.notify_updates(&updates).await {
+                warn!("failed to send update notification for ingested quests: {e}");
+            }
+        }
+        info!(
+            "sent update notifications for {} ingested quests from region {}",
+            updates.len(),
+            payload.region
+        );
+    }
+
+    let retried = delivery::retry_due(
+        &state.notifiers,
+        &payload.quests,
+        &payload.region,
+        &stored,
+        &storage::availability(&stored, &state.locales),
+        Utc::now(),
+    )
+    .await;
+    state
+        .store
+        .lock()
+        .await
+        .record_deliveries(&retried)
+        .map_err(storage_err)?;
+
     if new_only.is_empty() {
         return Ok(Json(IngestResponse {
             accepted: 0,
@@ -109,27 +222,12 @@ async fn ingest_handler(
     }
 
     if !state.notifiers.is_empty() {
-        let mut new_quest_ids: Vec<String> = new_only
-            .iter()
-            .map(|q| {
-                q.id.split(':')
-                    .next_back()
-                    .unwrap_or(q.id.as_str())
-                    .to_string()
-            })
-            .collect();
+        let mut new_quest_ids: Vec<String> =
+            new_only.iter().map(|q| q.base_id().to_string()).collect();
 
         // build base-id set from already stored to suppress cross-region duplicates
-        let seen_base: HashSet<String> = stored
-            .iter()
-            .map(|q| {
-                q.id.split(':')
-                    .next_back()
-                    .unwrap_or(q.id.as_str())
-                    .to_string()
-            })
-            .collect();
-        new_quest_ids.retain(|id| !seen_base.contains(id));
+        let seen_base: HashSet<&str> = stored.iter().map(StoredQuest::base_id).collect();
+        new_quest_ids.retain(|id| !seen_base.contains(id.as_str()));
 
         let full_new_quests: Vec<_> = payload
             .quests
@@ -138,27 +236,68 @@ async fn ingest_handler(
             .cloned()
             .collect();
 
+        let availability = storage::availability(stored.iter().chain(&new_only), &state.locales);
         if !full_new_quests.is_empty() {
+            delivery::announce_new(
+                &state.notifiers,
+                &full_new_quests,
+                &mut new_only,
+                &availability,
+                Utc::now(),
+            )
+            .await;
+            info!(
+                "sent notifications for {} new ingested quests from region {}",
+                full_new_quests.len(),
+                payload.region
+            );
+        }
+
+        // known quests the agent's region lists for the first time
+        let added: Vec<StoredQuest> = new_only
+            .iter()
+            .filter(|q| seen_base.contains(q.base_id()))
+            .cloned()
+            .collect();
+        let region_updates = storage::find_region_updates(&added, &availability);
+        if !region_updates.is_empty() {
             for notifier in state.notifiers.iter() {
-                if let Err(e) = notifier.notify_full(&full_new_quests).await {
-                    warn!("failed to send notification for ingested quests: {e}");
+                if let Err(e) = notifier.notify_updates(&region_updates).await {
+                    warn!("failed to send region update notification for ingested quests: {e}");
                 }
             }
             info!(
-                "sent notifications for {} new ingested quests from region {}",
-                full_new_quests.len(),
+                "{} known quests are now also listed for region {}",
+                region_updates.len(),
                 payload.region
             );
         }
+
+        let new_excluded: Vec<ExcludedQuest> = payload
+            .excluded_quests
+            .iter()
+            .filter(|eq| new_quest_ids.contains(&format!("{EXCLUDED_ID_PREFIX}{}", eq.id)))
+            .cloned()
+            .collect();
+
+        if !new_excluded.is_empty() {
+            for notifier in state.notifiers.iter() {
+                if let Err(e) = notifier
+                    .notify_excluded(&new_excluded, &payload.region)
+                    .await
+                {
+                    warn!("failed to send excluded notification for ingested quests: {e}");
+                }
+            }
+        }
     }
 
-    stored.extend(new_only.iter().cloned());
-    let merged = crate::utils::dedupe_by_key(&stored, |q| q.id.clone());
-    storage::save_quests(&merged)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let store = state.store.lock().await;
+    store.upsert(&new_only).map_err(storage_err)?;
+    let after = store.load().map_err(storage_err)?.len();
 
     Ok(Json(IngestResponse {
         accepted: new_only.len(),
-        deduped: merged.len().saturating_sub(before),
+        deduped: after.saturating_sub(before),
     }))
 }
@@ -1,5 +1,14 @@
+pub mod accounts;
+pub mod archive;
+pub mod budget;
+pub mod checkpoint;
 pub mod client;
+pub mod delivery;
+pub mod diagnostics;
 pub mod ingest;
+pub mod progress;
+pub mod retention;
+pub mod source;
 pub mod storage;
 pub mod webhook;
 
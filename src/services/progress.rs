@@ -0,0 +1,178 @@
+use crate::models::{hours_to_duration, Quest};
+use crate::services::webhook::WebhookNotifier;
+use crate::utils::{parse_datetime, read_json_file, write_json_file};
+use chrono::{Duration, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+
+/// Last seen `user_status` of one account on one quest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressSnapshot {
+    pub user_id: String,
+    pub quest_id: String,
+    pub enrolled_at: String,
+    pub completed_at: Option<String>,
+    pub claimed_at: Option<String>,
+    /// Whether the "claim expiring" reminder was already sent
+    #[serde(default)]
+    pub expiry_warned: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressKind {
+    Enrolled,
+    /// Completed, reward not claimed yet
+    Completed,
+    /// Completed but unclaimed, and `rewards_expire_at` is close
+    ClaimExpiring,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProgressEvent {
+    pub kind: ProgressKind,
+    pub quest_id: String,
+    pub quest_name: String,
+    pub game_name: String,
+    pub account: String,
+    pub claim_by: Option<String>,
+}
+
+/// Diffs the account's own `user_status` across cycles and reports
+/// enrolments, completions and soon-to-expire unclaimed rewards.
+pub struct ProgressTracker {
+    path: String,
+    warn_before: Duration,
+    notifier: WebhookNotifier,
+    snapshots: Mutex<HashMap<(String, String), ProgressSnapshot>>,
+}
+
+impl ProgressTracker {
+    #[must_use]
+    pub fn new(path: &str, warn_before_hours: u64, notifier: WebhookNotifier) -> Self {
+        // an unreadable file is re-seeded rather than announcing every status again
+        let snapshots: Option<Vec<ProgressSnapshot>> = if Path::new(path).exists() {
+            read_json_file(path)
+                .map_err(|e| warn!("failed to read quest progress file {path}, re-seeding: {e}"))
+                .ok()
+        } else {
+            None
+        };
+        Self {
+            path: path.to_string(),
+            // out-of-range values are rejected when the config is loaded
+            warn_before: hours_to_duration(warn_before_hours).unwrap_or(Duration::MAX),
+            notifier,
+            snapshots: Mutex::new(
+                snapshots
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|s| ((s.user_id.clone(), s.quest_id.clone()), s))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Record the `user_status` of `quests` fetched by `account` and return
+    /// what changed since the previous observation. The first statuses seen
+    /// for a user are recorded without announcing them.
+    pub fn observe(&self, quests: &[Quest], account: &str) -> Vec<ProgressEvent> {
+        let now = Utc::now();
+        let mut snapshots = self
+            .snapshots
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let known_users: HashSet<String> = snapshots.keys().map(|(user, _)| user.clone()).collect();
+        let mut seeded: HashSet<String> = HashSet::new();
+        let mut events = Vec::new();
+
+        for quest in quests {
+            let Some(status) = &quest.user_status else {
+                continue;
+            };
+            let key = (status.user_id.clone(), quest.config.id.clone());
+            let previous = snapshots.get(&key);
+            let was_completed = previous.is_some_and(|p| p.completed_at.is_some());
+            let mut expiry_warned = previous.is_some_and(|p| p.expiry_warned);
+
+            let event = |kind| ProgressEvent {
+                kind,
+                quest_id: quest.config.id.clone(),
+                quest_name: quest.config.messages.quest_name.clone(),
+                game_name: quest.config.messages.game_title.clone(),
+                account: account.to_string(),
+                claim_by: quest.config.rewards_config.rewards_expire_at.clone(),
+            };
+
+            let unclaimed = status.completed_at.is_some() && status.claimed_at.is_none();
+            if known_users.contains(&status.user_id) {
+                // a quest seen for the first time is reported as it is now,
+                // not as a fresh enrolment
+                if previous.is_none() && status.completed_at.is_none() {
+                    events.push(event(ProgressKind::Enrolled));
+                }
+                if unclaimed && !was_completed {
+                    events.push(event(ProgressKind::Completed));
+                }
+            } else {
+                seeded.insert(status.user_id.clone());
+            }
+
+            let claim_deadline = quest
+                .config
+                .rewards_config
+                .rewards_expire_at
+                .as_deref()
+                .and_then(parse_datetime);
+            if unclaimed
+                && !expiry_warned
+                && claim_deadline.is_some_and(|t| t > now && t - now <= self.warn_before)
+            {
+                events.push(event(ProgressKind::ClaimExpiring));
+                expiry_warned = true;
+            }
+
+            snapshots.insert(
+                key,
+                ProgressSnapshot {
+                    user_id: status.user_id.clone(),
+                    quest_id: quest.config.id.clone(),
+                    enrolled_at: status.enrolled_at.clone(),
+                    completed_at: status.completed_at.clone(),
+                    claimed_at: status.claimed_at.clone(),
+                    expiry_warned,
+                },
+            );
+        }
+
+        for user in &seeded {
+            info!(
+                "seeded quest progress of user {user} (account {account}); changes will be announced from now on"
+            );
+        }
+
+        let all: Vec<_> = snapshots.values().cloned().collect();
+        if let Err(e) = write_json_file(&self.path, &all) {
+            warn!("failed to save quest progress to {}: {e}", self.path);
+        }
+
+        events
+    }
+
+    /// Observe `quests` and send any resulting events to the progress webhook.
+    ///
+    /// # Errors
+    /// Returns error if the webhook request fails.
+    pub async fn track(&self, quests: &[Quest], account: &str) -> Result<(), String> {
+        let events = self.observe(quests, account);
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.notifier
+            .notify_progress(&events)
+            .await
+            .map_err(|e| format!("failed to send progress notifications: {e}"))
+    }
+}
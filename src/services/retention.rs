@@ -0,0 +1,247 @@
+use crate::models::{days_to_duration, Config, StorageError, StoredQuest};
+use crate::services::storage::QuestStore;
+use crate::utils::{parse_datetime, write_atomic};
+use chrono::{DateTime, Duration, Utc};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// A stored quest removed by retention, as written to the archive file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrunedQuest {
+    pub pruned_at: String,
+    #[serde(flatten)]
+    pub quest: StoredQuest,
+}
+
+/// Which stored quests are old enough to drop.
+///
+/// A quest is finished once its claim window closed (or `expires_at` passed,
+/// when there is no claim window), or when it was noticed gone from its
+/// locale, whichever comes first. Only finished quests are ever removed: a
+/// quest Discord still lists would otherwise be announced again as new.
+pub struct RetentionPolicy {
+    /// Remove quests this long after they finished
+    prune_after: Duration,
+    /// Keep at most this many entries per region, dropping the longest
+    /// finished first; live quests are kept even above the cap
+    max_per_region: Option<usize>,
+    /// Append removed entries to this JSON file instead of discarding them
+    archive_path: Option<String>,
+}
+
+impl RetentionPolicy {
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            // out-of-range values are rejected when the config is loaded
+            prune_after: days_to_duration(config.retention_prune_after_days())
+                .unwrap_or(Duration::MAX),
+            max_per_region: config.retention_max_per_region(),
+            archive_path: config.retention_archive_path().map(ToString::to_string),
+        }
+    }
+
+    /// When `quest` finished, if it has.
+    fn finished_at(quest: &StoredQuest, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let bounds = quest.lifecycle_bounds();
+        let closed = bounds
+            .expires_at
+            .max(bounds.rewards_expire_at)
+            .filter(|t| *t <= now);
+        let gone = quest
+            .ended
+            .as_ref()
+            .and_then(|e| parse_datetime(&e.detected_at));
+        match (closed, gone) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// The entries of `quests` the policy removes at `now`.
+    #[must_use]
+    pub fn select(&self, quests: &[StoredQuest], now: DateTime<Utc>) -> Vec<StoredQuest> {
+        // a cutoff before the earliest representable time prunes nothing by age
+        let cutoff = now
+            .checked_sub_signed(self.prune_after)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let mut pruned: HashSet<&str> = HashSet::new();
+        // per region: entries kept so far, and the finished ones among them
+        let mut kept: HashMap<&str, usize> = HashMap::new();
+        let mut finished: HashMap<&str, Vec<(DateTime<Utc>, &str)>> = HashMap::new();
+
+        for quest in quests {
+            let finished_at = Self::finished_at(quest, now);
+            if finished_at.is_some_and(|t| t <= cutoff) {
+                pruned.insert(&quest.id);
+                continue;
+            }
+            let region = quest.region().unwrap_or_default();
+            *kept.entry(region).or_default() += 1;
+            if let Some(t) = finished_at {
+                finished.entry(region).or_default().push((t, &quest.id));
+            }
+        }
+
+        if let Some(cap) = self.max_per_region {
+            for (region, count) in kept {
+                let excess = count.saturating_sub(cap);
+                if let Some(entries) = finished.get_mut(region).filter(|_| excess > 0) {
+                    entries.sort_unstable();
+                    pruned.extend(entries.iter().take(excess).map(|(_, id)| *id));
+                }
+            }
+        }
+
+        quests
+            .iter()
+            .filter(|q| pruned.contains(q.id.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Remove the entries selected at `now` from `store`, archiving them
+    /// first if an archive file is configured. Returns the removed entries.
+    ///
+    /// # Errors
+    /// Returns `StorageError` if the store cannot be read or written, or the
+    /// archive cannot be written; nothing is removed in the latter case.
+    pub fn apply(
+        &self,
+        store: &dyn QuestStore,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<StoredQuest>, StorageError> {
+        let selected = self.select(&store.load()?, now);
+        if selected.is_empty() {
+            debug!("retention: nothing to prune in {}", store.describe());
+            return Ok(selected);
+        }
+
+        if let Some(path) = &self.archive_path {
+            archive_pruned(path, &selected, now)?;
+        }
+        let ids: HashSet<&str> = selected.iter().map(|q| q.id.as_str()).collect();
+        let removed = store.prune(&|q| !ids.contains(q.id.as_str()))?;
+        info!(
+            "retention: pruned {removed} stored quests from {}{}",
+            store.describe(),
+            self.archive_path
+                .as_deref()
+                .map(|p| format!(" (archived to {p})"))
+                .unwrap_or_default()
+        );
+        Ok(selected)
+    }
+}
+
+/// Append `quests` to the archive file at `path`.
+fn archive_pruned(
+    path: &str,
+    quests: &[StoredQuest],
+    now: DateTime<Utc>,
+) -> Result<(), StorageError> {
+    let mut archived: Vec<PrunedQuest> = if Path::new(path).exists() {
+        serde_json::from_str(&fs::read_to_string(path)?).map_err(|e| StorageError::Corrupt {
+            path: path.to_string(),
+            reason: e.to_string(),
+        })?
+    } else {
+        Vec::new()
+    };
+    let pruned_at = now.to_rfc3339();
+    archived.extend(quests.iter().map(|quest| PrunedQuest {
+        pruned_at: pruned_at.clone(),
+        quest: quest.clone(),
+    }));
+    write_atomic(path, serde_json::to_string_pretty(&archived)?.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EndReason, QuestEnd};
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        parse_datetime(timestamp).unwrap()
+    }
+
+    fn stored(id: &str, expires_at: &str, rewards_expire_at: Option<&str>) -> StoredQuest {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": "Quest",
+            "expires_at": expires_at,
+            "rewards_expire_at": rewards_expire_at,
+            "game_name": "Game",
+        }))
+        .unwrap()
+    }
+
+    fn policy(prune_after: Duration, max_per_region: Option<usize>) -> RetentionPolicy {
+        RetentionPolicy {
+            prune_after,
+            max_per_region,
+            archive_path: None,
+        }
+    }
+
+    fn ids(quests: &[StoredQuest]) -> Vec<&str> {
+        quests.iter().map(|q| q.id.as_str()).collect()
+    }
+
+    #[test]
+    fn prunes_quests_finished_at_or_before_the_cutoff() {
+        let now = at("2026-03-01T00:00:00Z");
+        let mut pulled = stored("en-US:pulled", "2026-12-01T00:00:00Z", None);
+        pulled.ended = Some(QuestEnd {
+            reason: EndReason::PulledEarly,
+            detected_at: "2026-01-15T00:00:00Z".to_string(),
+        });
+        let quests = vec![
+            stored("en-US:old", "2026-01-01T00:00:00Z", None),
+            stored("en-US:at-cutoff", "2026-01-30T00:00:00Z", None),
+            stored("en-US:recent", "2026-02-15T00:00:00Z", None),
+            // the claim window is what counts once there is one
+            stored(
+                "en-US:claimable",
+                "2026-01-01T00:00:00Z",
+                Some("2026-02-20T00:00:00Z"),
+            ),
+            stored("en-US:live", "2026-12-01T00:00:00Z", None),
+            pulled,
+        ];
+
+        let pruned = policy(Duration::days(30), None).select(&quests, now);
+        assert_eq!(
+            ids(&pruned),
+            ["en-US:old", "en-US:at-cutoff", "en-US:pulled"]
+        );
+    }
+
+    #[test]
+    fn an_unrepresentable_cutoff_prunes_nothing_by_age() {
+        let quests = vec![stored("en-US:old", "2000-01-01T00:00:00Z", None)];
+        let pruned = policy(Duration::MAX, None).select(&quests, at("2026-03-01T00:00:00Z"));
+        assert!(pruned.is_empty());
+    }
+
+    #[test]
+    fn the_cap_drops_the_longest_finished_and_never_live_quests() {
+        let now = at("2026-03-01T00:00:00Z");
+        let quests = vec![
+            stored("en-US:a", "2026-02-20T00:00:00Z", None),
+            stored("en-US:b", "2026-02-10T00:00:00Z", None),
+            stored("en-US:c", "2026-02-25T00:00:00Z", None),
+            stored("en-US:live", "2026-12-01T00:00:00Z", None),
+            stored("ja-JP:live-1", "2026-12-01T00:00:00Z", None),
+            stored("ja-JP:live-2", "2026-12-01T00:00:00Z", None),
+            stored("ja-JP:live-3", "2026-12-01T00:00:00Z", None),
+        ];
+
+        let pruned = policy(Duration::days(30), Some(2)).select(&quests, now);
+        assert_eq!(ids(&pruned), ["en-US:a", "en-US:b"]);
+    }
+}
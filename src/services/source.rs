@@ -0,0 +1,68 @@
+use crate::models::{ClientError, DecodedQuests};
+use crate::services::accounts::AccountPool;
+use crate::services::archive::ArchivedResponse;
+use crate::services::{decode_quests_response, QuestClient};
+use chrono::{DateTime, Utc};
+use log::info;
+
+/// Where a collector cycle gets its quests from.
+pub enum QuestSource<'a> {
+    /// Discord itself, through the account pool
+    Live {
+        client: &'a QuestClient,
+        accounts: &'a AccountPool,
+    },
+    /// Archived responses, using the newest one per locale at or before `until`
+    Replay {
+        entries: &'a [ArchivedResponse],
+        until: DateTime<Utc>,
+    },
+}
+
+impl QuestSource<'_> {
+    /// Fetch quests for `locale`, returning them with the name of the account
+    /// that saw them.
+    ///
+    /// # Errors
+    /// Returns the `ClientError` from the live fetch, or the error the archived
+    /// response decodes to. `ClientError::NotArchived` if nothing was archived
+    /// for the locale.
+    pub async fn fetch(&self, locale: &str) -> Result<(DecodedQuests, String), ClientError> {
+        match self {
+            Self::Live { client, accounts } => client
+                .fetch_quests_from_pool(accounts, locale)
+                .await
+                .map(|(quests, account)| (quests, account.name.clone())),
+            Self::Replay { entries, until } => {
+                let entry = entries
+                    .iter()
+                    .rev()
+                    .find(|e| e.locale == locale && e.fetched_at <= *until)
+                    .ok_or_else(|| ClientError::NotArchived(locale.to_string()))?;
+                info!(
+                    "replaying archived response for {locale} from {} (status {})",
+                    entry.fetched_at, entry.status
+                );
+                let decoded = decode_quests_response(
+                    entry.status,
+                    &reqwest::header::HeaderMap::new(),
+                    &entry.body,
+                    locale,
+                )?;
+                let account = entry
+                    .account
+                    .clone()
+                    .unwrap_or_else(|| "archive".to_string());
+                Ok((decoded, account))
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn decode_failures(&self) -> u64 {
+        match self {
+            Self::Live { client, .. } => client.decode_failures(),
+            Self::Replay { .. } => 0,
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use super::{upsert_into, QuestStore};
+use crate::models::{StorageError, StoredQuest};
+use crate::utils::{ensure_parent_dir, write_atomic};
+use log::{debug, info};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Keeps quests in a single JSON file, rewritten on every change.
+pub struct JsonStore {
+    path: String,
+    /// Serialises read-modify-write cycles on the file
+    lock: Mutex<()>,
+}
+
+impl JsonStore {
+    /// Open the file at `path`, checking that it can be read.
+    ///
+    /// # Errors
+    /// Returns `StorageError::Io` if the parent directory cannot be created
+    /// and `StorageError::Corrupt` if the file exists but isn't valid.
+    pub fn open(path: &str) -> Result<Self, StorageError> {
+        ensure_parent_dir(path)?;
+        let store = Self {
+            path: path.to_string(),
+            lock: Mutex::new(()),
+        };
+        store.read()?;
+        Ok(store)
+    }
+
+    fn read(&self) -> Result<Vec<StoredQuest>, StorageError> {
+        if !Path::new(&self.path).exists() {
+            return Ok(Vec::new());
+        }
+
+        // an existing but unreadable file must not look like "no quests seen",
+        // or every known quest would be announced again
+        let corrupt = |reason: String| StorageError::Corrupt {
+            path: self.path.clone(),
+            reason,
+        };
+        let content = fs::read_to_string(&self.path).map_err(|e| corrupt(e.to_string()))?;
+        if content.trim().is_empty() {
+            return Err(corrupt("file is empty".to_string()));
+        }
+        let quests = serde_json::from_str::<Vec<StoredQuest>>(&content)
+            .map_err(|e| corrupt(e.to_string()))?;
+        debug!("loaded {} stored quests from {}", quests.len(), self.path);
+        Ok(quests)
+    }
+
+    fn write(&self, quests: &[StoredQuest]) -> Result<(), StorageError> {
+        write_atomic(&self.path, serde_json::to_string_pretty(quests)?.as_bytes())?;
+        info!("saved {} quests to {}", quests.len(), self.path);
+        Ok(())
+    }
+
+    fn guard(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.lock
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl QuestStore for JsonStore {
+    fn describe(&self) -> String {
+        format!("json ({})", self.path)
+    }
+
+    fn load(&self) -> Result<Vec<StoredQuest>, StorageError> {
+        let _guard = self.guard();
+        self.read()
+    }
+
+    fn upsert(&self, quests: &[StoredQuest]) -> Result<(), StorageError> {
+        if quests.is_empty() {
+            return Ok(());
+        }
+        let _guard = self.guard();
+        let mut stored = self.read()?;
+        upsert_into(&mut stored, quests);
+        self.write(&stored)
+    }
+
+    fn prune(&self, keep: &dyn Fn(&StoredQuest) -> bool) -> Result<usize, StorageError> {
+        let _guard = self.guard();
+        let mut stored = self.read()?;
+        let before = stored.len();
+        stored.retain(|q| keep(q));
+        let removed = before - stored.len();
+        if removed > 0 {
+            self.write(&stored)?;
+        }
+        Ok(removed)
+    }
+}
@@ -0,0 +1,49 @@
+use super::{upsert_into, QuestStore};
+use crate::models::{StorageError, StoredQuest};
+use log::debug;
+use std::sync::Mutex;
+
+/// Keeps quests in process memory only; everything is forgotten on exit.
+#[derive(Default)]
+pub struct MemoryStore {
+    quests: Mutex<Vec<StoredQuest>>,
+}
+
+impl MemoryStore {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<StoredQuest>> {
+        // a panic while holding the lock can't leave the Vec half-written
+        self.quests
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl QuestStore for MemoryStore {
+    fn describe(&self) -> String {
+        "memory".to_string()
+    }
+
+    fn load(&self) -> Result<Vec<StoredQuest>, StorageError> {
+        let quests = self.lock().clone();
+        debug!("loaded {} quests from in-memory storage", quests.len());
+        Ok(quests)
+    }
+
+    fn upsert(&self, quests: &[StoredQuest]) -> Result<(), StorageError> {
+        let mut stored = self.lock();
+        upsert_into(&mut stored, quests);
+        debug!(
+            "saved {} quests to in-memory storage ({} total)",
+            quests.len(),
+            stored.len()
+        );
+        Ok(())
+    }
+
+    fn prune(&self, keep: &dyn Fn(&StoredQuest) -> bool) -> Result<usize, StorageError> {
+        let mut stored = self.lock();
+        let before = stored.len();
+        stored.retain(|q| keep(q));
+        Ok(before - stored.len())
+    }
+}
@@ -0,0 +1,555 @@
+mod json;
+mod memory;
+mod sqlite;
+
+pub use json::JsonStore;
+pub use memory::MemoryStore;
+pub use sqlite::SqliteStore;
+
+use crate::models::{
+    Availability, Quest, QuestChange, QuestEnd, QuestUpdate, StorageError, StoredQuest,
+    EXCLUDED_ID_PREFIX,
+};
+use chrono::{DateTime, Utc};
+use log::info;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Where known quests are kept between cycles. Entries are keyed by their
+/// region-prefixed id (`"en-US:123"`).
+pub trait QuestStore: Send + Sync {
+    /// Backend name and location, for logs
+    fn describe(&self) -> String;
+
+    /// Every stored quest.
+    ///
+    /// # Errors
+    /// Returns `StorageError` if the backend cannot be read.
+    fn load(&self) -> Result<Vec<StoredQuest>, StorageError>;
+
+    /// Insert `quests`, replacing stored entries with the same id.
+    ///
+    /// # Errors
+    /// Returns `StorageError` if the backend cannot be written.
+    fn upsert(&self, quests: &[StoredQuest]) -> Result<(), StorageError>;
+
+    /// Remove every entry `keep` returns false for; returns how many were removed.
+    ///
+    /// # Errors
+    /// Returns `StorageError` if the backend cannot be written.
+    fn prune(&self, keep: &dyn Fn(&StoredQuest) -> bool) -> Result<usize, StorageError>;
+
+    /// All regional entries of one quest, by its unprefixed id.
+    ///
+    /// # Errors
+    /// Returns `StorageError` if the backend cannot be read.
+    fn by_quest(&self, quest_id: &str) -> Result<Vec<StoredQuest>, StorageError> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .filter(|q| q.base_id() == quest_id)
+            .collect())
+    }
+
+    /// All entries stored for `region`.
+    ///
+    /// # Errors
+    /// Returns `StorageError` if the backend cannot be read.
+    fn by_region(&self, region: &str) -> Result<Vec<StoredQuest>, StorageError> {
+        let prefix = format!("{region}:");
+        Ok(self
+            .load()?
+            .into_iter()
+            .filter(|q| q.id.starts_with(&prefix))
+            .collect())
+    }
+
+    /// The entries of `quests` whose id is not stored yet.
+    ///
+    /// # Errors
+    /// Returns `StorageError` if the backend cannot be read.
+    fn find_new(&self, quests: &[StoredQuest]) -> Result<Vec<StoredQuest>, StorageError> {
+        Ok(find_new_quests(quests, &self.load()?))
+    }
+
+    /// Copy the `ended` marker of `ended_quests` onto the matching entries.
+    ///
+    /// # Errors
+    /// Returns `StorageError` if the backend cannot be read or written.
+    fn mark_ended(&self, ended_quests: &[StoredQuest]) -> Result<(), StorageError> {
+        let marked: Vec<StoredQuest> = self
+            .load()?
+            .into_iter()
+            .filter_map(|mut stored| {
+                let ended = ended_quests.iter().find(|q| q.id == stored.id)?;
+                stored.ended.clone_from(&ended.ended);
+                Some(stored)
+            })
+            .collect();
+        self.upsert(&marked)
+    }
+
+    /// Copy the delivery ledgers of `entries` onto the matching entries,
+    /// leaving anything else that changed since they were loaded alone.
+    ///
+    /// # Errors
+    /// Returns `StorageError` if the backend cannot be read or written.
+    fn record_deliveries(&self, entries: &[StoredQuest]) -> Result<(), StorageError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let recorded: Vec<StoredQuest> = self
+            .load()?
+            .into_iter()
+            .filter_map(|mut stored| {
+                let entry = entries.iter().find(|q| q.id == stored.id)?;
+                stored.deliveries.clone_from(&entry.deliveries);
+                Some(stored)
+            })
+            .collect();
+        self.upsert(&recorded)
+    }
+}
+
+/// The process-wide handle to the quest store. Everything that loads quests,
+/// decides what to announce and writes the result back (collector cycle,
+/// ingest handler, lifecycle scheduler) does so under `lock`, so concurrent
+/// callers can't act on each other's stale view of the store. The collector
+/// and the ingest handler release it while calling webhooks and take it
+/// again to record the outcome.
+#[derive(Clone)]
+pub struct StoreHandle {
+    store: Arc<dyn QuestStore>,
+    gate: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// Exclusive access to the store, released on drop.
+pub struct StoreGuard<'a> {
+    store: &'a (dyn QuestStore + 'static),
+    _gate: tokio::sync::MutexGuard<'a, ()>,
+}
+
+impl StoreHandle {
+    #[must_use]
+    pub fn new(store: Arc<dyn QuestStore>) -> Self {
+        Self {
+            store,
+            gate: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// Wait for exclusive access to the store.
+    pub async fn lock(&self) -> StoreGuard<'_> {
+        StoreGuard {
+            _gate: self.gate.lock().await,
+            store: self.store.as_ref(),
+        }
+    }
+}
+
+impl Deref for StoreGuard<'_> {
+    type Target = dyn QuestStore;
+
+    fn deref(&self) -> &Self::Target {
+        self.store
+    }
+}
+
+/// Open the backend named by `storage_type`.
+///
+/// # Errors
+/// Returns `StorageError::UnknownBackend` for an unsupported type, or the
+/// backend's error if it cannot be opened.
+pub fn open_store(storage_type: &str, storage_path: &str) -> Result<StoreHandle, StorageError> {
+    let store: Arc<dyn QuestStore> = match storage_type {
+        "memory" => Arc::new(MemoryStore::default()),
+        "json" => Arc::new(JsonStore::open(storage_path)?),
+        "sqlite" => Arc::new(SqliteStore::open(storage_path)?),
+        other => return Err(StorageError::UnknownBackend(other.to_string())),
+    };
+    info!("storage initialized - {}", store.describe());
+    Ok(StoreHandle::new(store))
+}
+
+/// Copy the entries of a JSON quest file into `store`, skipping ids it
+/// already has. Returns how many entries were imported.
+///
+/// # Errors
+/// Returns `StorageError` if the file cannot be read or the store written.
+pub fn import_json(store: &dyn QuestStore, path: &str) -> Result<usize, StorageError> {
+    let quests = JsonStore::open(path)?.load()?;
+    let new = store.find_new(&quests)?;
+    store.upsert(&new)?;
+    info!(
+        "imported {} of {} quests from {path} into {}",
+        new.len(),
+        quests.len(),
+        store.describe()
+    );
+    Ok(new.len())
+}
+
+/// Merge `quests` into `existing` by id, replacing entries in place and
+/// appending new ones.
+fn upsert_into(existing: &mut Vec<StoredQuest>, quests: &[StoredQuest]) {
+    for quest in quests {
+        match existing.iter_mut().find(|q| q.id == quest.id) {
+            Some(slot) => *slot = quest.clone(),
+            None => existing.push(quest.clone()),
+        }
+    }
+}
+
+#[must_use]
+pub fn filter_quests(quests: &[Quest], filter: &str) -> Vec<StoredQuest> {
+    quests
+        .iter()
+        .filter_map(|quest| {
+            let stored = StoredQuest::from(quest);
+
+            // a quest matches if any of its rewards does
+            if filter == "all" || stored.rewards.iter().any(|r| r.kind.filter_key() == filter) {
+                Some(stored)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[must_use]
+pub fn find_new_quests(
+    all_quests: &[StoredQuest],
+    stored_quests: &[StoredQuest],
+) -> Vec<StoredQuest> {
+    all_quests
+        .iter()
+        .filter(|q| !stored_quests.iter().any(|stored| stored.id == q.id))
+        .cloned()
+        .collect()
+}
+
+/// Known quests whose config changed since they were stored. A quest seen in
+/// several regions is reported once, and not at all if another region's entry
+/// already has the new fingerprint (the change was reported before).
+#[must_use]
+pub fn find_updated_quests(
+    all_quests: &[StoredQuest],
+    stored_quests: &[StoredQuest],
+) -> Vec<QuestUpdate> {
+    let mut updates: Vec<QuestUpdate> = Vec::new();
+
+    for quest in all_quests {
+        let Some(previous) = stored_quests.iter().find(|s| s.id == quest.id) else {
+            continue;
+        };
+        let changes = quest.changes_since(previous);
+        if changes.is_empty() {
+            continue;
+        }
+        let base = quest.base_id();
+        let already_known = stored_quests
+            .iter()
+            .any(|s| s.base_id() == base && s.fingerprint == quest.fingerprint);
+        let already_listed = updates.iter().any(|u| u.quest.base_id() == base);
+        if !already_known && !already_listed {
+            updates.push(QuestUpdate {
+                quest: quest.clone(),
+                changes,
+            });
+        }
+    }
+    updates
+}
+
+/// Regions each quest is currently listed in, keyed by unprefixed quest id.
+/// Ended and excluded entries don't count.
+#[must_use]
+pub fn regions_by_quest<'a>(
+    entries: impl IntoIterator<Item = &'a StoredQuest>,
+) -> BTreeMap<String, BTreeSet<String>> {
+    let mut regions: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for entry in entries {
+        if entry.ended.is_some() || entry.excluded {
+            continue;
+        }
+        if let Some(region) = entry.region() {
+            regions
+                .entry(entry.base_id().to_string())
+                .or_default()
+                .insert(region.to_string());
+        }
+    }
+    regions
+}
+
+/// Availability of every quest in `entries`, out of the `checked` locales.
+#[must_use]
+pub fn availability<'a>(
+    entries: impl IntoIterator<Item = &'a StoredQuest>,
+    checked: &[String],
+) -> BTreeMap<String, Availability> {
+    regions_by_quest(entries)
+        .into_iter()
+        .map(|(id, regions)| {
+            let availability = Availability::new(&regions, checked);
+            (id, availability)
+        })
+        .collect()
+}
+
+/// One update per known quest that `added` lists in new regions, with the
+/// quest's availability from `availability` (which must already include
+/// `added`).
+#[must_use]
+pub fn find_region_updates(
+    added: &[StoredQuest],
+    availability: &BTreeMap<String, Availability>,
+) -> Vec<QuestUpdate> {
+    let mut updates: Vec<QuestUpdate> = Vec::new();
+    for entry in added {
+        let Some(region) = entry.region() else {
+            continue;
+        };
+        let Some(now) = availability.get(entry.base_id()) else {
+            continue;
+        };
+        match updates
+            .iter_mut()
+            .find(|u| u.quest.base_id() == entry.base_id())
+        {
+            Some(update) => {
+                if let Some(QuestChange::Regions { added, .. }) = update.changes.first_mut() {
+                    added.push(region.to_string());
+                }
+            }
+            None => updates.push(QuestUpdate {
+                quest: entry.clone(),
+                changes: vec![QuestChange::Regions {
+                    added: vec![region.to_string()],
+                    now: now.clone(),
+                }],
+            }),
+        }
+    }
+    updates
+}
+
+/// Replace stored entries whose config differs from the fresh version,
+/// keeping their recorded lifecycle state, delivery ledger and `seen_by`.
+/// Returns the replaced entries.
+pub fn refresh_known_quests(
+    stored_quests: &mut [StoredQuest],
+    all_quests: &[StoredQuest],
+) -> Vec<StoredQuest> {
+    let mut refreshed = Vec::new();
+    for stored in stored_quests.iter_mut() {
+        if let Some(fresh) = all_quests
+            .iter()
+            .find(|q| q.id == stored.id && q.fingerprint != stored.fingerprint)
+        {
+            let state = stored.state;
+            let deliveries = std::mem::take(&mut stored.deliveries);
+            let seen_by = stored.seen_by.take();
+            *stored = fresh.clone();
+            stored.state = state.or(fresh.state);
+            stored.deliveries = deliveries;
+            stored.seen_by = seen_by.or_else(|| fresh.seen_by.clone());
+            refreshed.push(stored.clone());
+        }
+    }
+    refreshed
+}
+
+/// The entries of `removed` to announce as ended: one per quest, and none
+/// for a quest whose end is already recorded in another region of `store`.
+///
+/// # Errors
+/// Returns `StorageError` if the store cannot be read.
+pub fn ended_to_announce(
+    store: &dyn QuestStore,
+    removed: &[StoredQuest],
+) -> Result<Vec<StoredQuest>, StorageError> {
+    let mut announce: Vec<StoredQuest> = Vec::new();
+    for quest in removed {
+        let announced_before = store
+            .by_quest(quest.base_id())?
+            .iter()
+            .any(|s| s.ended.is_some())
+            || announce.iter().any(|a| a.base_id() == quest.base_id());
+        if !announced_before {
+            announce.push(quest.clone());
+        }
+    }
+    Ok(announce)
+}
+
+/// Stored quests of `locale` that are missing from its latest response,
+/// returned with `ended` set. `present_ids` are the unprefixed ids in the
+/// response, excluded quests included. A quest that moved to the excluded
+/// list is still listed, so it doesn't count as ended.
+#[must_use]
+pub fn find_removed_quests(
+    stored_quests: &[StoredQuest],
+    locale: &str,
+    present_ids: &HashSet<String>,
+    now: DateTime<Utc>,
+) -> Vec<StoredQuest> {
+    let prefix = format!("{locale}:");
+    stored_quests
+        .iter()
+        .filter(|q| q.ended.is_none())
+        .filter(|q| {
+            q.id.strip_prefix(&prefix).is_some_and(|id| {
+                !present_ids.contains(id)
+                    && !present_ids.contains(&format!("{EXCLUDED_ID_PREFIX}{id}"))
+            })
+        })
+        .map(|q| {
+            let mut ended = q.clone();
+            ended.ended = Some(QuestEnd {
+                reason: q.end_reason(now),
+                detected_at: now.to_rfc3339(),
+            });
+            ended
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EndReason;
+
+    fn stored(id: &str) -> StoredQuest {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": "Quest",
+            "expires_at": "2026-02-01T00:00:00Z",
+            "game_name": "Game",
+            "excluded": id.contains(EXCLUDED_ID_PREFIX),
+        }))
+        .unwrap()
+    }
+
+    fn now() -> DateTime<Utc> {
+        crate::utils::parse_datetime("2026-01-15T00:00:00Z").unwrap()
+    }
+
+    fn ids(quests: &[StoredQuest]) -> Vec<&str> {
+        quests.iter().map(|q| q.id.as_str()).collect()
+    }
+
+    #[test]
+    fn only_unlisted_entries_of_the_locale_are_removed() {
+        let mut already_ended = stored("en-US:5");
+        already_ended.ended = Some(QuestEnd {
+            reason: EndReason::Expired,
+            detected_at: now().to_rfc3339(),
+        });
+        let quests = vec![
+            stored("en-US:1"),
+            stored("en-US:2"),
+            stored("en-US:3"),
+            stored("en-US:excluded-4"),
+            already_ended,
+            stored("ja-JP:2"),
+        ];
+        // 3 moved to the excluded list, 4 is no longer excluded
+        let present: HashSet<String> = ["1".to_string(), "excluded-3".to_string()].into();
+
+        let removed = find_removed_quests(&quests, "en-US", &present, now());
+        assert_eq!(ids(&removed), ["en-US:2", "en-US:excluded-4"]);
+        assert_eq!(
+            removed[0].ended.as_ref().map(|e| e.reason),
+            Some(EndReason::PulledEarly)
+        );
+
+        // the collector ends the first and only drops the second
+        let (excluded, ended): (Vec<_>, Vec<_>) = removed.into_iter().partition(|q| q.excluded);
+        assert_eq!(ids(&ended), ["en-US:2"]);
+        assert_eq!(ids(&excluded), ["en-US:excluded-4"]);
+    }
+
+    #[test]
+    fn a_quest_ending_in_several_regions_is_announced_once() {
+        let store = MemoryStore::default();
+        store
+            .upsert(&[stored("en-US:2"), stored("ja-JP:2")])
+            .unwrap();
+        let present = HashSet::new();
+        let removed: Vec<StoredQuest> = ["en-US", "ja-JP"]
+            .iter()
+            .flat_map(|locale| find_removed_quests(&store.load().unwrap(), locale, &present, now()))
+            .collect();
+        assert_eq!(removed.len(), 2);
+
+        let announce = ended_to_announce(&store, &removed).unwrap();
+        assert_eq!(ids(&announce), ["en-US:2"]);
+    }
+
+    #[test]
+    fn an_end_recorded_in_another_region_is_not_announced_again() {
+        let store = MemoryStore::default();
+        store
+            .upsert(&[stored("en-US:2"), stored("ja-JP:2")])
+            .unwrap();
+        let present = HashSet::new();
+
+        let first =
+            find_removed_quests(&store.by_region("en-US").unwrap(), "en-US", &present, now());
+        assert_eq!(ended_to_announce(&store, &first).unwrap().len(), 1);
+        store.mark_ended(&first).unwrap();
+
+        let later =
+            find_removed_quests(&store.by_region("ja-JP").unwrap(), "ja-JP", &present, now());
+        assert_eq!(ids(&later), ["ja-JP:2"]);
+        assert!(ended_to_announce(&store, &later).unwrap().is_empty());
+    }
+
+    #[test]
+    fn availability_ignores_ended_and_excluded_entries() {
+        let mut ended = stored("de-DE:1");
+        ended.ended = Some(QuestEnd {
+            reason: EndReason::PulledEarly,
+            detected_at: now().to_rfc3339(),
+        });
+        let entries = [
+            stored("en-US:1"),
+            stored("ja-JP:1"),
+            ended,
+            stored("de-DE:excluded-1"),
+        ];
+        let locales = |codes: &[&str]| codes.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        let two = availability(&entries, &locales(&["en-US", "ja-JP"]));
+        assert_eq!(two["1"], Availability::Global);
+
+        let three = availability(&entries, &locales(&["en-US", "ja-JP", "de-DE"]));
+        assert_eq!(
+            three["1"],
+            Availability::Regions(locales(&["en-US", "ja-JP"]))
+        );
+
+        // one checked locale is never called global
+        let one = availability(&entries[..1], &locales(&["en-US"]));
+        assert_eq!(one["1"].describe(), "en-US");
+    }
+
+    #[test]
+    fn new_regions_of_a_quest_make_one_update() {
+        let added = [stored("ja-JP:1"), stored("ko-KR:1")];
+        let availability = BTreeMap::from([("1".to_string(), Availability::Global)]);
+
+        let updates = find_region_updates(&added, &availability);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(
+            updates[0].changes,
+            [QuestChange::Regions {
+                added: vec!["ja-JP".to_string(), "ko-KR".to_string()],
+                now: Availability::Global,
+            }]
+        );
+    }
+}
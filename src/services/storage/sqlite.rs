@@ -0,0 +1,289 @@
+use super::QuestStore;
+use crate::models::{StorageError, StoredQuest};
+use chrono::Utc;
+use log::{debug, info};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Schema changes, applied in order. `PRAGMA user_version` records how many
+/// have run; append new entries, never edit old ones.
+const MIGRATIONS: &[&str] = &[
+    // 1: one row per (region, quest), with history timestamps and raw JSON
+    "CREATE TABLE quests (
+        region TEXT NOT NULL,
+        quest_id TEXT NOT NULL,
+        data TEXT NOT NULL,
+        raw TEXT,
+        first_seen_at TEXT NOT NULL,
+        last_seen_at TEXT NOT NULL,
+        ended_at TEXT,
+        PRIMARY KEY (region, quest_id)
+    );
+    CREATE INDEX idx_quests_quest_id ON quests (quest_id);",
+];
+
+/// Keeps quests in a SQLite database keyed by (region, quest id), along with
+/// when each was first and last seen and the quest JSON as Discord sent it.
+pub struct SqliteStore {
+    path: String,
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open (or create) the database at `path` and bring its schema up to date.
+    ///
+    /// # Errors
+    /// Returns `StorageError` if the database cannot be opened or migrated.
+    pub fn open(path: &str) -> Result<Self, StorageError> {
+        crate::utils::ensure_parent_dir(path)?;
+        let mut conn = Connection::open(path)?;
+        migrate(&mut conn)?;
+        Ok(Self {
+            path: path.to_string(),
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), StorageError> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version >= MIGRATIONS.len() {
+        return Ok(());
+    }
+
+    let tx = conn.transaction()?;
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        info!("applying storage migration {}", index + 1);
+        tx.execute_batch(sql)?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Split a stored id (`"en-US:123"`) into its region and quest id.
+fn split_id(id: &str) -> (&str, &str) {
+    id.split_once(':').unwrap_or(("", id))
+}
+
+fn decode_rows(
+    rows: impl Iterator<Item = rusqlite::Result<String>>,
+) -> Result<Vec<StoredQuest>, StorageError> {
+    rows.map(|data| Ok(serde_json::from_str(&data?)?)).collect()
+}
+
+impl QuestStore for SqliteStore {
+    fn describe(&self) -> String {
+        format!("sqlite ({})", self.path)
+    }
+
+    fn load(&self) -> Result<Vec<StoredQuest>, StorageError> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT data FROM quests ORDER BY first_seen_at")?;
+        let quests = decode_rows(stmt.query_map([], |row| row.get(0))?)?;
+        debug!("loaded {} stored quests from {}", quests.len(), self.path);
+        Ok(quests)
+    }
+
+    /// `last_seen_at` and `raw` only move forward for entries built from a
+    /// fresh response (those carry `raw`); state-only updates keep them.
+    fn upsert(&self, quests: &[StoredQuest]) -> Result<(), StorageError> {
+        if quests.is_empty() {
+            return Ok(());
+        }
+        let now = Utc::now().to_rfc3339();
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO quests (region, quest_id, data, raw, first_seen_at, last_seen_at, ended_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6)
+                 ON CONFLICT (region, quest_id) DO UPDATE SET
+                    data = excluded.data,
+                    raw = COALESCE(excluded.raw, quests.raw),
+                    last_seen_at = CASE WHEN excluded.raw IS NOT NULL
+                        THEN excluded.last_seen_at ELSE quests.last_seen_at END,
+                    ended_at = excluded.ended_at",
+            )?;
+            for quest in quests {
+                let (region, quest_id) = split_id(&quest.id);
+                stmt.execute(params![
+                    region,
+                    quest_id,
+                    serde_json::to_string(quest)?,
+                    quest.raw.as_ref().map(ToString::to_string),
+                    now,
+                    quest.ended.as_ref().map(|e| e.detected_at.as_str()),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        debug!("saved {} quests to {}", quests.len(), self.path);
+        Ok(())
+    }
+
+    fn prune(&self, keep: &dyn Fn(&StoredQuest) -> bool) -> Result<usize, StorageError> {
+        let doomed: Vec<String> = self
+            .load()?
+            .into_iter()
+            .filter(|q| !keep(q))
+            .map(|q| q.id)
+            .collect();
+        if doomed.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare("DELETE FROM quests WHERE region = ?1 AND quest_id = ?2")?;
+            for id in &doomed {
+                let (region, quest_id) = split_id(id);
+                stmt.execute(params![region, quest_id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(doomed.len())
+    }
+
+    fn by_quest(&self, quest_id: &str) -> Result<Vec<StoredQuest>, StorageError> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT data FROM quests WHERE quest_id = ?1")?;
+        let rows = stmt.query_map([quest_id], |row| row.get(0))?;
+        decode_rows(rows)
+    }
+
+    fn by_region(&self, region: &str) -> Result<Vec<StoredQuest>, StorageError> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT data FROM quests WHERE region = ?1")?;
+        let rows = stmt.query_map([region], |row| row.get(0))?;
+        decode_rows(rows)
+    }
+
+    fn find_new(&self, quests: &[StoredQuest]) -> Result<Vec<StoredQuest>, StorageError> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT 1 FROM quests WHERE region = ?1 AND quest_id = ?2")?;
+        let mut new = Vec::new();
+        for quest in quests {
+            let (region, quest_id) = split_id(&quest.id);
+            let known = stmt
+                .query_row(params![region, quest_id], |_| Ok(()))
+                .optional()?
+                .is_some();
+            if !known {
+                new.push(quest.clone());
+            }
+        }
+        Ok(new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::storage::refresh_known_quests;
+
+    fn memory_store() -> SqliteStore {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        SqliteStore {
+            path: ":memory:".to_string(),
+            conn: Mutex::new(conn),
+        }
+    }
+
+    fn stored(id: &str, fingerprint: &str, seen_by: &str) -> StoredQuest {
+        let mut quest: StoredQuest = serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": "Quest",
+            "expires_at": "2026-02-01T00:00:00Z",
+            "game_name": "Game",
+            "seen_by": seen_by,
+            "fingerprint": fingerprint,
+        }))
+        .unwrap();
+        quest.raw = Some(serde_json::json!({ "id": quest.base_id() }));
+        quest
+    }
+
+    fn seen_at(store: &SqliteStore, quest_id: &str) -> (String, String) {
+        store
+            .conn()
+            .query_row(
+                "SELECT first_seen_at, last_seen_at FROM quests WHERE quest_id = ?1",
+                [quest_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap()
+    }
+
+    fn user_version(conn: &Connection) -> usize {
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn migrates_an_unversioned_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(user_version(&conn), 0);
+        migrate(&mut conn).unwrap();
+        assert_eq!(user_version(&conn), MIGRATIONS.len());
+        conn.execute_batch(
+            "SELECT region, quest_id, data, raw, first_seen_at, last_seen_at, ended_at FROM quests",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn reopening_a_current_database_keeps_its_rows() {
+        let path = std::env::temp_dir().join(format!("qwesty-migrate-{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        SqliteStore::open(path)
+            .unwrap()
+            .upsert(&[stored("en-US:1", "v2:a", "main")])
+            .unwrap();
+
+        let reopened = SqliteStore::open(path).unwrap();
+        assert_eq!(user_version(&reopened.conn()), MIGRATIONS.len());
+        assert_eq!(reopened.load().unwrap().len(), 1);
+        drop(reopened);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn refreshing_a_quest_keeps_first_seen_and_seen_by() {
+        let store = memory_store();
+        store.upsert(&[stored("en-US:1", "v2:a", "main")]).unwrap();
+        let (first_seen, last_seen) = seen_at(&store, "1");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        let mut known = store.load().unwrap();
+        let refreshed = refresh_known_quests(&mut known, &[stored("en-US:1", "v2:b", "alt")]);
+        store.upsert(&refreshed).unwrap();
+
+        let (first_seen_now, last_seen_now) = seen_at(&store, "1");
+        assert_eq!(first_seen_now, first_seen);
+        assert!(last_seen_now > last_seen);
+        let quest = &store.load().unwrap()[0];
+        assert_eq!(quest.fingerprint.as_deref(), Some("v2:b"));
+        assert_eq!(quest.seen_by.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn state_only_updates_leave_last_seen_alone() {
+        let store = memory_store();
+        store.upsert(&[stored("en-US:1", "v2:a", "main")]).unwrap();
+        let before = seen_at(&store, "1");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        let mut quest = store.load().unwrap().remove(0);
+        quest.raw = None;
+        store.upsert(&[quest]).unwrap();
+        assert_eq!(seen_at(&store, "1"), before);
+    }
+}
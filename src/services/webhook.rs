@@ -1,5 +1,10 @@
-use crate::models::{NotifyError, Quest};
-use crate::utils::{parse_color, parse_timestamp, DEFAULT_REWARD_URL};
+use crate::models::{
+    Availability, CycleSummary, EndReason, ExcludedQuest, JoinOperator, NotifyError, Quest,
+    QuestUpdate, StoredQuest, TaskSet,
+};
+use crate::services::delivery::DeliveryPolicy;
+use crate::services::progress::{ProgressEvent, ProgressKind};
+use crate::utils::{fnv1a_hex, parse_color, parse_timestamp, DEFAULT_REWARD_URL};
 use log::{debug, error, info};
 use serde_json::json;
 use std::time::Duration;
@@ -13,6 +18,16 @@ pub struct WebhookNotifier {
     webhook_url: String,
     client: reqwest::Client,
     message: Option<String>,
+    /// Also announce quests from `excluded_quests`
+    notify_excluded: bool,
+    /// Announce config changes to quests that were already notified
+    notify_updates: bool,
+    /// Announce quests that were pulled early or expired
+    notify_ended: bool,
+    /// Render payloads to stdout instead of sending them
+    dry_run: bool,
+    /// Retries of failed new-quest announcements
+    delivery: DeliveryPolicy,
 }
 
 impl WebhookNotifier {
@@ -22,43 +37,112 @@ impl WebhookNotifier {
             webhook_url,
             client: reqwest::Client::new(),
             message,
+            notify_excluded: false,
+            notify_updates: true,
+            notify_ended: false,
+            dry_run: false,
+            delivery: DeliveryPolicy::default(),
         }
     }
 
-    /// Send notification for full quest details
+    #[must_use]
+    pub fn with_excluded_notifications(mut self, enabled: bool) -> Self {
+        self.notify_excluded = enabled;
+        self
+    }
+
+    #[must_use]
+    pub fn with_update_notifications(mut self, enabled: bool) -> Self {
+        self.notify_updates = enabled;
+        self
+    }
+
+    #[must_use]
+    pub fn with_ended_notifications(mut self, enabled: bool) -> Self {
+        self.notify_ended = enabled;
+        self
+    }
+
+    #[must_use]
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    #[must_use]
+    pub fn with_delivery_policy(mut self, policy: DeliveryPolicy) -> Self {
+        self.delivery = policy;
+        self
+    }
+
+    #[must_use]
+    pub fn delivery_policy(&self) -> &DeliveryPolicy {
+        &self.delivery
+    }
+
+    #[must_use]
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("default")
+    }
+
+    /// Identifies this webhook in the delivery ledger. Derived from the URL,
+    /// so renaming a webhook keeps its history and the URL is never stored.
+    #[must_use]
+    pub fn ledger_key(&self) -> String {
+        fnv1a_hex(self.webhook_url.as_bytes())
+    }
+
+    /// Confirm the webhook still exists with a GET on its URL.
     ///
     /// # Errors
-    /// Returns `NotifyError` if webhook request fails.
-    pub async fn notify_full(&self, quests: &[Quest]) -> Result<(), NotifyError> {
-        if quests.is_empty() {
-            debug!("no quests to notify");
-            return Ok(());
-        }
+    /// Returns `NotifyError::HttpStatus` if Discord does not return the webhook,
+    /// or `NotifyError::SendFailed` if the request fails.
+    pub async fn check(&self) -> Result<String, NotifyError> {
+        let response = self
+            .client
+            .get(&self.webhook_url)
+            .send()
+            .await
+            .map_err(NotifyError::SendFailed)?;
 
-        for quest in quests {
-            self.send_full_quest_notification(quest).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(NotifyError::HttpStatus(status.as_u16()));
         }
 
-        info!("sent {} full notifications", quests.len());
-        Ok(())
+        let webhook: serde_json::Value = response.json().await.map_err(NotifyError::SendFailed)?;
+        Ok(webhook
+            .get("name")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("unnamed")
+            .to_string())
     }
 
-    async fn send_full_quest_notification(&self, quest: &Quest) -> Result<(), NotifyError> {
+    /// Send the full notification for one quest, with an "Available in" line
+    /// when its availability is known.
+    ///
+    /// # Errors
+    /// Returns `NotifyError` if the request fails or the webhook rejects it.
+    pub async fn send_full_quest_notification(
+        &self,
+        quest: &Quest,
+        availability: Option<&Availability>,
+    ) -> Result<(), NotifyError> {
         let config = &quest.config;
         let color = parse_color(&config.colors.primary, 0x0058_65F2);
         let hero_url = format!("{}{}", DISCORD_CDN, config.assets.hero);
         let quest_url = format!("https://discord.com/quests/{}", config.id);
 
-        let platforms = config
-            .task_config_v2
-            .as_ref()
-            .map_or_else(|| String::from("Cross Platform"), build_platform_list);
-
-        let tasks_desc = build_tasks_desc(config.task_config_v2.as_ref());
+        let task_set = TaskSet::from_config(config);
+        let platforms = build_platform_list(task_set.as_ref());
+        let tasks_desc = build_tasks_desc(task_set.as_ref());
         let rewards_desc = build_rewards_desc(&config.rewards_config);
         let reward_media_url = reward_media_url(&config.rewards_config);
         let features_str = format_features(&config.features);
-        let quest_info = build_quest_info(config, &platforms, &features_str);
+        let mut quest_info = build_quest_info(config, &platforms, &features_str);
+        if let Some(availability) = availability {
+            quest_info.push_str(&format!("**Available in:** {}\n", availability.describe()));
+        }
 
         let content = WebhookContent {
             config,
@@ -71,8 +155,126 @@ impl WebhookNotifier {
             color,
         };
 
-        let mut container = build_webhook_container(&content);
+        let container = build_webhook_container(&content);
+        self.send_container(container, &config.id, &config.messages.quest_name)
+            .await
+    }
+
+    /// Send quests that the account was excluded from, if this webhook opted in.
+    ///
+    /// # Errors
+    /// Returns `NotifyError` if webhook request fails.
+    pub async fn notify_excluded(
+        &self,
+        quests: &[ExcludedQuest],
+        region: &str,
+    ) -> Result<(), NotifyError> {
+        if !self.notify_excluded || quests.is_empty() {
+            return Ok(());
+        }
+
+        for quest in quests {
+            let container = build_excluded_container(quest, region);
+            self.send_container(container, &quest.id, quest.name())
+                .await?;
+        }
+
+        info!("sent {} excluded quest notifications", quests.len());
+        Ok(())
+    }
+
+    /// Send what changed about quests that were already announced, if this
+    /// webhook wants updates.
+    ///
+    /// # Errors
+    /// Returns `NotifyError` if webhook request fails.
+    pub async fn notify_updates(&self, updates: &[QuestUpdate]) -> Result<(), NotifyError> {
+        if !self.notify_updates || updates.is_empty() {
+            return Ok(());
+        }
+
+        for update in updates {
+            let container = build_update_container(update);
+            self.send_container(container, &update.quest.id, &update.quest.name)
+                .await?;
+        }
+
+        info!("sent {} quest update notifications", updates.len());
+        Ok(())
+    }
+
+    /// Send quests that disappeared from `region`, if this webhook opted in.
+    ///
+    /// # Errors
+    /// Returns `NotifyError` if webhook request fails.
+    pub async fn notify_ended(
+        &self,
+        quests: &[StoredQuest],
+        region: &str,
+    ) -> Result<(), NotifyError> {
+        if !self.notify_ended || quests.is_empty() {
+            return Ok(());
+        }
+
+        for quest in quests {
+            let container = build_ended_container(quest, region);
+            self.send_container(container, &quest.id, &quest.name)
+                .await?;
+        }
 
+        info!("sent {} ended quest notifications", quests.len());
+        Ok(())
+    }
+
+    /// Announce quests that were discovered before `starts_at` and are now live.
+    ///
+    /// # Errors
+    /// Returns `NotifyError` if webhook request fails.
+    pub async fn notify_now_live(&self, quests: &[StoredQuest]) -> Result<(), NotifyError> {
+        for quest in quests {
+            let container = build_live_container(quest);
+            self.send_container(container, &quest.id, &quest.name)
+                .await?;
+        }
+
+        info!("sent {} quest live notifications", quests.len());
+        Ok(())
+    }
+
+    /// Send personal quest progress updates for the tracked account(s).
+    ///
+    /// # Errors
+    /// Returns `NotifyError` if webhook request fails.
+    pub async fn notify_progress(&self, events: &[ProgressEvent]) -> Result<(), NotifyError> {
+        for event in events {
+            let container = build_progress_container(event);
+            self.send_container(container, &event.quest_id, &event.quest_name)
+                .await?;
+        }
+
+        info!("sent {} quest progress notifications", events.len());
+        Ok(())
+    }
+
+    /// Post the locales that failed in a collector cycle. Cycles where every
+    /// locale was fetched are not posted.
+    ///
+    /// # Errors
+    /// Returns `NotifyError` if webhook request fails.
+    pub async fn notify_cycle_summary(&self, summary: &CycleSummary) -> Result<(), NotifyError> {
+        if summary.failed().next().is_none() {
+            return Ok(());
+        }
+        let container = build_summary_container(summary);
+        self.send_container(container, "-", "cycle summary").await
+    }
+
+    async fn send_container(
+        &self,
+        mut container: serde_json::Value,
+        quest_id: &str,
+        quest_name: &str,
+    ) -> Result<(), NotifyError> {
         // if a per-webhook `message` is configured, inject it as the first
         // text component inside the container instead of using the top-level
         // `content` field, which Discord rejects when using
@@ -94,7 +296,7 @@ impl WebhookNotifier {
 
         let payload = json!({ "components": [container], "flags": 32768 });
 
-        let notifier_name = self.name.as_deref().unwrap_or("default");
+        let notifier_name = self.display_name();
         debug!(
             "sending webhook (notifier={}) type={}, accent={}",
             notifier_name, container["type"], container["accent_color"]
@@ -104,6 +306,14 @@ impl WebhookNotifier {
             serde_json::to_string_pretty(&payload).unwrap_or_default()
         );
 
+        if self.dry_run {
+            println!(
+                "# notifier={notifier_name} quest={quest_id}\n{}",
+                serde_json::to_string_pretty(&payload).unwrap_or_default()
+            );
+            return Ok(());
+        }
+
         let webhook_url_with_params = format!("{}?with_components=true", self.webhook_url);
 
         let response = self
@@ -124,15 +334,12 @@ impl WebhookNotifier {
                 notifier_name,
                 serde_json::to_string_pretty(&payload).unwrap_or_default()
             );
-            return Ok(());
+            return Err(NotifyError::HttpStatus(status.as_u16()));
         }
 
         debug!("webhook response: status={status}");
 
-        info!(
-            "sent full notification for quest: {} to notifier: {}",
-            config.messages.quest_name, notifier_name
-        );
+        info!("sent notification for quest: {quest_name} to notifier: {notifier_name}");
 
         // small pause to avoid hammering the webhook rate limits
         () = sleep(Duration::from_millis(250)).await;
@@ -140,25 +347,13 @@ impl WebhookNotifier {
     }
 }
 
-fn build_platform_list(task_config: &crate::models::QuestTaskConfigV2) -> String {
-    let mut platforms = Vec::new();
-
-    for task in task_config.tasks.values() {
-        let platform = match task.r#type.as_str() {
-            "PLAY_ON_DESKTOP" => "🖥️ PC",
-            "PLAY_ON_XBOX" => "🎮 Xbox",
-            "PLAY_ON_PLAYSTATION" => "🎮 PlayStation",
-            "WATCH_VIDEO" => "📺 Desktop",
-            "WATCH_VIDEO_ON_MOBILE" => "📱 Mobile",
-            _ => continue,
-        };
-
-        if !platforms.contains(&platform) {
-            platforms.push(platform);
-        }
+fn build_platform_list(task_set: Option<&TaskSet>) -> String {
+    let platforms = task_set.map(TaskSet::platforms).unwrap_or_default();
+    if platforms.is_empty() {
+        String::from("Cross Platform")
+    } else {
+        platforms.join(", ")
     }
-
-    platforms.join(", ")
 }
 
 fn reward_media_url(rewards_config: &crate::models::QuestRewardsConfig) -> String {
@@ -179,61 +374,62 @@ fn reward_media_url(rewards_config: &crate::models::QuestRewardsConfig) -> Strin
         )
 }
 
-fn build_tasks_desc(task_config: Option<&crate::models::QuestTaskConfigV2>) -> String {
+fn build_tasks_desc(task_set: Option<&TaskSet>) -> String {
     use std::fmt::Write;
 
-    task_config.map_or_else(
-        || String::from("## Tasks\n\nN/A"),
-        |cfg| {
-            let mut desc =
-                String::from("## Tasks\n\nUsers must complete any of the following tasks\n");
-            for (task_type, task) in &cfg.tasks {
-                let platform = match task_type.as_str() {
-                    "PLAY_ON_DESKTOP" => "Play on desktop",
-                    "PLAY_ON_XBOX" => "Play on Xbox",
-                    "PLAY_ON_PLAYSTATION" => "Play on PlayStation",
-                    "WATCH_VIDEO" => "Watch video",
-                    "WATCH_VIDEO_ON_MOBILE" => "Watch video on mobile",
-                    _ => task_type.as_str(),
-                };
-                // target is in seconds; display minutes rounded up (59s -> 1 minute)
-                let minutes = task.target.div_ceil(60);
-                let unit = if minutes == 1 { "minute" } else { "minutes" };
-                let _ = writeln!(desc, "- {platform} ({minutes} {unit})");
-            }
-            desc
-        },
-    )
+    let Some(set) = task_set.filter(|set| !set.tasks.is_empty()) else {
+        return String::from("## Tasks\n\nN/A");
+    };
+
+    let quantifier = match set.join {
+        JoinOperator::Any => "any",
+        JoinOperator::All => "all",
+    };
+    let mut desc = format!("## Tasks\n\nUsers must complete {quantifier} of the following tasks\n");
+    for task in &set.tasks {
+        let _ = writeln!(desc, "- {}", task.summary());
+    }
+    desc
 }
 
 fn build_rewards_desc(rewards_config: &crate::models::QuestRewardsConfig) -> String {
     use std::fmt::Write;
 
-    rewards_config.rewards.first().map_or_else(
-        || String::from("## Rewards\n\nN/A"),
-        |reward| {
-            let reward_type = match reward.r#type {
-                1 => "In-game Code",
-                2 => "Profile Decoration",
-                3 => "Avatar Decoration",
-                4 => "Virtual Currency",
-                _ => "Unknown",
-            };
+    if rewards_config.rewards.is_empty() {
+        return String::from("## Rewards\n\nN/A");
+    }
 
-            let mut desc = format!(
-                "## Rewards\n\n**Type:** {reward_type}\n**SKU ID:** `{}`",
-                reward.sku_id
-            );
+    let mut desc = String::from("## Rewards\n");
+    for reward in &rewards_config.rewards {
+        let _ = write!(
+            desc,
+            "\n**Type:** {}\n**Name:** {}\n**SKU ID:** `{}`\n",
+            reward.r#type.label(),
+            reward.messages.name,
+            reward.sku_id
+        );
+        if let Some(orb_qty) = reward.orb_quantity {
+            let _ = writeln!(desc, "**Orb Amount:** {orb_qty}");
+        }
+    }
 
-            let _ = writeln!(desc, "\n**Name:** {}", reward.messages.name);
+    if let Some(expires) = &rewards_config.rewards_expire_at {
+        let _ = writeln!(desc, "\n**Claim by:** <t:{}:D>", parse_timestamp(expires));
+    }
 
-            if let Some(orb_qty) = reward.orb_quantity {
-                let _ = writeln!(desc, "\n**Orb Amount:** {orb_qty}");
-            }
+    desc
+}
 
-            desc
-        },
-    )
+fn reward_names(rewards_config: &crate::models::QuestRewardsConfig) -> String {
+    if rewards_config.rewards.is_empty() {
+        return "Unknown Reward".to_string();
+    }
+    rewards_config
+        .rewards
+        .iter()
+        .map(|r| r.messages.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 fn format_features(features: &[u32]) -> String {
@@ -397,3 +593,276 @@ fn build_webhook_container(content: &WebhookContent) -> serde_json::Value {
         ]
     })
 }
+
+fn build_excluded_container(quest: &ExcludedQuest, region: &str) -> serde_json::Value {
+    use std::fmt::Write;
+
+    let quest_url = format!("https://discord.com/quests/{}", quest.id);
+    let mut details = format!(
+        "**Region:** {region}\n**Reason:** {}\n",
+        quest.reason_text()
+    );
+    if let Some(config) = &quest.config {
+        let _ = write!(
+            details,
+            "**Game:** {}\n**Reward:** {}\n**Ends at:** <t:{}:D>\n",
+            config.messages.game_title,
+            reward_names(&config.rewards_config),
+            parse_timestamp(&config.expires_at)
+        );
+    }
+
+    json!({
+        "type": 17,
+        "accent_color": 0x0080_8080,
+        "spoiler": false,
+        "components": [
+            {
+                "type": 10,
+                "content": format!("## 🚫 Excluded quest spotted\n[{}]({})", quest.name(), quest_url)
+            },
+            {
+                "type": 14,
+                "divider": true,
+                "spacing": 1
+            },
+            {
+                "type": 10,
+                "content": details
+            },
+            {
+                "type": 10,
+                "content": format!("-# Quest ID: `{}`", quest.id)
+            }
+        ]
+    })
+}
+
+fn build_live_container(quest: &StoredQuest) -> serde_json::Value {
+    let base_id = quest.base_id();
+    let quest_url = format!("https://discord.com/quests/{base_id}");
+    let rewards = quest
+        .rewards
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let details = format!(
+        "**Game:** {}\n**Reward:** {}\n**Ends at:** <t:{}:D> (<t:{}:R>)\n",
+        quest.game_name,
+        if rewards.is_empty() {
+            "Unknown"
+        } else {
+            &rewards
+        },
+        parse_timestamp(&quest.expires_at),
+        parse_timestamp(&quest.expires_at)
+    );
+
+    json!({
+        "type": 17,
+        "accent_color": 0x0057_F287,
+        "spoiler": false,
+        "components": [
+            {
+                "type": 10,
+                "content": format!("## 🟢 Quest is now live\n[{}]({})", quest.name, quest_url)
+            },
+            {
+                "type": 14,
+                "divider": true,
+                "spacing": 1
+            },
+            {
+                "type": 10,
+                "content": details
+            },
+            {
+                "type": 10,
+                "content": format!("-# Quest ID: `{base_id}`")
+            }
+        ]
+    })
+}
+
+fn build_progress_container(event: &ProgressEvent) -> serde_json::Value {
+    let quest_url = format!("https://discord.com/quests/{}", event.quest_id);
+    let (title, accent) = match event.kind {
+        ProgressKind::Enrolled => ("📝 Enrolled in quest", 0x0058_65F2),
+        ProgressKind::Completed => ("✅ Quest completed, reward ready to claim", 0x0057_F287),
+        ProgressKind::ClaimExpiring => ("⏰ Unclaimed reward expires soon", 0x00FE_E75C),
+    };
+    let mut details = format!(
+        "**Game:** {}\n**Account:** {}\n",
+        event.game_name, event.account
+    );
+    if event.kind != ProgressKind::Enrolled {
+        if let Some(claim_by) = &event.claim_by {
+            let ts = parse_timestamp(claim_by);
+            details.push_str(&format!("**Claim by:** <t:{ts}:D> (<t:{ts}:R>)\n"));
+        }
+    }
+
+    json!({
+        "type": 17,
+        "accent_color": accent,
+        "spoiler": false,
+        "components": [
+            {
+                "type": 10,
+                "content": format!("## {title}\n[{}]({})", event.quest_name, quest_url)
+            },
+            {
+                "type": 14,
+                "divider": true,
+                "spacing": 1
+            },
+            {
+                "type": 10,
+                "content": details
+            },
+            {
+                "type": 10,
+                "content": format!("-# Quest ID: `{}`", event.quest_id)
+            }
+        ]
+    })
+}
+
+fn build_update_container(update: &QuestUpdate) -> serde_json::Value {
+    use std::fmt::Write;
+
+    let quest = &update.quest;
+    let base_id = quest.base_id();
+    let quest_url = format!("https://discord.com/quests/{base_id}");
+    let mut changes = String::new();
+    for change in &update.changes {
+        let _ = writeln!(changes, "- {}", change.describe());
+    }
+    let details = format!(
+        "**Game:** {}\n**Ends at:** <t:{}:D>\n",
+        quest.game_name,
+        parse_timestamp(&quest.expires_at)
+    );
+
+    json!({
+        "type": 17,
+        "accent_color": 0x00FE_E75C,
+        "spoiler": false,
+        "components": [
+            {
+                "type": 10,
+                "content": format!("## 🔄 Quest updated\n[{}]({})", quest.name, quest_url)
+            },
+            {
+                "type": 14,
+                "divider": true,
+                "spacing": 1
+            },
+            {
+                "type": 10,
+                "content": format!("## Changes\n\n{changes}")
+            },
+            {
+                "type": 10,
+                "content": details
+            },
+            {
+                "type": 10,
+                "content": format!("-# Quest ID: `{base_id}`")
+            }
+        ]
+    })
+}
+
+fn build_ended_container(quest: &StoredQuest, region: &str) -> serde_json::Value {
+    let base_id = quest.base_id();
+    let quest_url = format!("https://discord.com/quests/{base_id}");
+    let reason = quest
+        .ended
+        .as_ref()
+        .map_or(EndReason::Expired, |e| e.reason);
+    let (title, accent, expiry_label) = match reason {
+        EndReason::PulledEarly => ("🛑 Quest pulled early", 0x00ED_4245, "Was due to end"),
+        EndReason::Expired => ("⌛ Quest ended", 0x0080_8080, "Ended"),
+    };
+    let mut details = format!(
+        "**Region:** {region}\n**Game:** {}\n**{expiry_label}:** <t:{}:R>\n",
+        quest.game_name,
+        parse_timestamp(&quest.expires_at)
+    );
+    if let Some(claim_by) = &quest.rewards_expire_at {
+        details.push_str(&format!(
+            "**Claim rewards by:** <t:{}:D>\n",
+            parse_timestamp(claim_by)
+        ));
+    }
+
+    json!({
+        "type": 17,
+        "accent_color": accent,
+        "spoiler": false,
+        "components": [
+            {
+                "type": 10,
+                "content": format!("## {title}\n[{}]({})", quest.name, quest_url)
+            },
+            {
+                "type": 14,
+                "divider": true,
+                "spacing": 1
+            },
+            {
+                "type": 10,
+                "content": details
+            },
+            {
+                "type": 10,
+                "content": format!("-# Quest ID: `{base_id}`")
+            }
+        ]
+    })
+}
+
+fn build_summary_container(summary: &CycleSummary) -> serde_json::Value {
+    use std::fmt::Write;
+
+    let failed: Vec<_> = summary.failed().collect();
+    let mut lines = String::new();
+    for (locale, outcome) in &failed {
+        let mut detail = outcome.detail();
+        if detail.chars().count() > 150 {
+            detail = format!("{}…", detail.chars().take(150).collect::<String>());
+        }
+        let _ = writeln!(lines, "- `{locale}`: **{}** ({detail})", outcome.label());
+    }
+
+    json!({
+        "type": 17,
+        "accent_color": if summary.all_failed() { 0x00ED_4245 } else { 0x00FE_E75C },
+        "spoiler": false,
+        "components": [
+            {
+                "type": 10,
+                "content": format!(
+                    "## ⚠️ {} of {} locales failed",
+                    failed.len(),
+                    summary.outcomes.len()
+                )
+            },
+            {
+                "type": 14,
+                "divider": true,
+                "spacing": 1
+            },
+            {
+                "type": 10,
+                "content": lines
+            },
+            {
+                "type": 10,
+                "content": "-# Stored quests of these locales were left untouched this cycle"
+            }
+        ]
+    })
+}
@@ -0,0 +1,9 @@
+/// 64-bit FNV-1a hash of `bytes` as 16 hex digits. Stable across builds and
+/// platforms, unlike `std::hash`, so it can be stored.
+#[must_use]
+pub fn fnv1a_hex(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
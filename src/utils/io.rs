@@ -1,6 +1,7 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::fs;
+use std::io::Write;
 use std::path::Path;
 
 /// Ensure parent directory exists, creating it if needed.
@@ -26,7 +27,7 @@ pub fn read_json_file<T: DeserializeOwned>(path: &str) -> Result<T, Box<dyn std:
     Ok(t)
 }
 
-/// Write data as JSON to file.
+/// Write data as JSON to file, atomically (see `write_atomic`).
 ///
 /// # Errors
 /// Returns error if file cannot be written or JSON serialization fails.
@@ -34,8 +35,23 @@ pub fn write_json_file<T: Serialize + ?Sized>(
     path: &str,
     data: &T,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    ensure_parent_dir(path)?;
     let json = serde_json::to_string_pretty(data)?;
-    fs::write(path, json)?;
+    write_atomic(path, json.as_bytes())?;
     Ok(())
 }
+
+/// Write `contents` to a temporary file next to `path`, flush it to disk and
+/// rename it over `path`, so a crash never leaves a half-written file behind.
+///
+/// # Errors
+/// Returns `std::io::Error` if any step fails; `path` is then left untouched.
+pub fn write_atomic(path: &str, contents: &[u8]) -> Result<(), std::io::Error> {
+    ensure_parent_dir(path)?;
+    let tmp = format!("{path}.tmp");
+    {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)
+}
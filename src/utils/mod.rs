@@ -1,11 +1,13 @@
 pub mod collections;
 pub mod color;
 pub mod constants;
+pub mod hash;
 pub mod io;
 pub mod time;
 
 pub use collections::*;
 pub use color::*;
 pub use constants::*;
+pub use hash::*;
 pub use io::*;
 pub use time::*;
@@ -2,3 +2,10 @@
 pub fn parse_timestamp(iso_timestamp: &str) -> i64 {
     chrono::DateTime::parse_from_rfc3339(iso_timestamp).map_or(0, |dt| dt.timestamp())
 }
+
+#[must_use]
+pub fn parse_datetime(iso_timestamp: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(iso_timestamp)
+        .ok()
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+}
@@ -0,0 +1,121 @@
+//! Runs `QuestClient` against the bundled mock Discord server.
+
+use qwesty::accounts::AccountPool;
+use qwesty::{ClientError, Config, QuestClient};
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+/// The mock server process, killed when dropped.
+struct MockDiscord {
+    child: Child,
+    port: u16,
+}
+
+impl MockDiscord {
+    async fn start(extra_args: &[&str]) -> Self {
+        let port = free_port();
+        // wrapped right away so the process is killed on every exit path
+        let mock = Self {
+            child: Command::new(env!("CARGO_BIN_EXE_mock_discord"))
+                .args(["--port", &port.to_string()])
+                .args([
+                    "--fixtures",
+                    concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/quests"),
+                ])
+                .args(extra_args)
+                .stderr(Stdio::null())
+                .spawn()
+                .expect("failed to start mock_discord"),
+            port,
+        };
+        for _ in 0..100 {
+            if tokio::net::TcpStream::connect(("127.0.0.1", port))
+                .await
+                .is_ok()
+            {
+                return mock;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("mock_discord did not start listening on port {port}");
+    }
+
+    fn config(&self, accounts: &str) -> Config {
+        toml::from_str(&format!(
+            r#"
+            [discord]
+            api_base = "http://127.0.0.1:{}/api"
+            {accounts}
+
+            [notifier]
+            "#,
+            self.port
+        ))
+        .expect("test config should parse")
+    }
+}
+
+impl Drop for MockDiscord {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("no free port")
+}
+
+#[tokio::test]
+async fn fetches_the_fixture_for_each_locale() {
+    let mock = MockDiscord::start(&[]).await;
+    let config = mock.config(r#"token = "test-token""#);
+    let client = QuestClient::new(config.api_url());
+    let pool = AccountPool::from_config(&config);
+
+    let (en, _) = client.fetch_quests_from_pool(&pool, "en-US").await.unwrap();
+    assert_eq!(en.quests.len(), 2);
+    assert!(en.undecoded.is_empty());
+
+    let (ko, account) = client.fetch_quests_from_pool(&pool, "ko-KR").await.unwrap();
+    assert_eq!(ko.quests.len(), 3);
+    assert_eq!(ko.excluded.len(), 1);
+    assert_eq!(account.token, "test-token");
+}
+
+#[tokio::test]
+async fn fails_over_to_the_next_account_on_401() {
+    let mock = MockDiscord::start(&["--reject-token", "bad-token"]).await;
+    let config = mock.config(
+        r#"
+        [[discord.accounts]]
+        name = "bad"
+        token = "bad-token"
+
+        [[discord.accounts]]
+        name = "good"
+        token = "good-token"
+        "#,
+    );
+    let client = QuestClient::new(config.api_url());
+    let pool = AccountPool::from_config(&config);
+
+    let (_, account) = client.fetch_quests_from_pool(&pool, "en-US").await.unwrap();
+    assert_eq!(account.name, "good");
+    assert_eq!(pool.active_count(), 1);
+}
+
+#[tokio::test]
+async fn reports_no_active_account_when_every_token_is_rejected() {
+    let mock = MockDiscord::start(&["--reject-token", "bad-token"]).await;
+    let config = mock.config(r#"token = "bad-token""#);
+    let client = QuestClient::new(config.api_url());
+    let pool = AccountPool::from_config(&config);
+
+    let result = client.fetch_quests_from_pool(&pool, "en-US").await;
+    assert!(matches!(result, Err(ClientError::NoActiveAccount(_))));
+}
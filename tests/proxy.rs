@@ -0,0 +1,124 @@
+//! Per-locale proxy selection, with local stand-ins for Discord and the proxy.
+
+use qwesty::accounts::AccountPool;
+use qwesty::{Config, ProxyEntry, QuestClient};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const EMPTY_RESPONSE: &str = r#"{"quests":[],"excluded_quests":[]}"#;
+
+/// Answers every request with an empty quests response and remembers the
+/// request heads it got. An HTTP proxy receives the absolute URL in the
+/// request line, so the stand-in works as either Discord or the proxy.
+struct StandIn {
+    port: u16,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl StandIn {
+    async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut head = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => head.extend_from_slice(&buf[..n]),
+                    }
+                }
+                seen.lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&head).to_lowercase());
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{EMPTY_RESPONSE}",
+                    EMPTY_RESPONSE.len()
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        Self { port, requests }
+    }
+
+    /// A config whose API base points at this stand-in.
+    fn config(&self) -> Config {
+        toml::from_str(&format!(
+            r#"
+            [discord]
+            token = "test-token"
+            api_base = "http://127.0.0.1:{}/api"
+
+            [notifier]
+            "#,
+            self.port
+        ))
+        .expect("test config should parse")
+    }
+
+    fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[tokio::test]
+async fn routes_only_the_proxied_locales_through_the_proxy() {
+    let discord = StandIn::start().await;
+    let proxy = StandIn::start().await;
+    let config = discord.config();
+    let client = QuestClient::new(config.api_url())
+        .with_proxies(&[ProxyEntry {
+            url: format!("http://127.0.0.1:{}", proxy.port),
+            locales: Some(vec!["ko-KR".to_string()]),
+        }])
+        .unwrap();
+    let pool = AccountPool::from_config(&config);
+
+    client.fetch_quests_from_pool(&pool, "ko-KR").await.unwrap();
+    client.fetch_quests_from_pool(&pool, "en-US").await.unwrap();
+
+    let proxied = proxy.requests();
+    assert_eq!(proxied.len(), 1);
+    assert!(proxied[0].starts_with(&format!(
+        "get http://127.0.0.1:{}/api/v10/quests/@me",
+        discord.port
+    )));
+    assert!(proxied[0].contains("x-discord-locale: ko-kr"));
+
+    let direct = discord.requests();
+    assert_eq!(direct.len(), 1);
+    assert!(direct[0].contains("x-discord-locale: en-us"));
+}
+
+#[tokio::test]
+async fn an_entry_without_locales_proxies_every_other_locale() {
+    let discord = StandIn::start().await;
+    let korea = StandIn::start().await;
+    let fallback = StandIn::start().await;
+    let config = discord.config();
+    let client = QuestClient::new(config.api_url())
+        .with_proxies(&[
+            ProxyEntry {
+                url: format!("http://127.0.0.1:{}", korea.port),
+                locales: Some(vec!["ko-KR".to_string()]),
+            },
+            ProxyEntry {
+                url: format!("http://127.0.0.1:{}", fallback.port),
+                locales: None,
+            },
+        ])
+        .unwrap();
+    let pool = AccountPool::from_config(&config);
+
+    for locale in ["ko-KR", "en-US", "de-DE"] {
+        client.fetch_quests_from_pool(&pool, locale).await.unwrap();
+    }
+
+    assert_eq!(korea.requests().len(), 1);
+    assert_eq!(fallback.requests().len(), 2);
+    assert!(discord.requests().is_empty());
+}